@@ -2,10 +2,11 @@ use colored::Colorize;
 use semver::{Version, VersionReq};
 use serde::Deserialize;
 
+use crate::config;
 use crate::state::AppState;
 
-/// URL where advisories are hosted.
-const ADVISORIES_URL: &str =
+/// Default URL where advisories are hosted (`advisories.url` in config).
+pub const DEFAULT_ADVISORIES_URL: &str =
     "https://raw.githubusercontent.com/iii-hq/iii-cli/main/advisories.json";
 
 /// The top-level advisories document.
@@ -46,7 +47,10 @@ pub struct MatchedAdvisory {
 pub async fn fetch_advisories(
     client: &reqwest::Client,
 ) -> Result<AdvisoriesDocument, reqwest::Error> {
-    let response = client.get(ADVISORIES_URL).send().await?;
+    let response = client
+        .get(config::current().advisories_url())
+        .send()
+        .await?;
 
     if !response.status().is_success() {
         // Return empty advisories on non-200 responses
@@ -119,11 +123,7 @@ pub fn print_advisory_warnings(matched: &[MatchedAdvisory]) {
         );
 
         // Show CLI command to update
-        let cmd = crate::registry::REGISTRY
-            .iter()
-            .find(|s| s.name == m.advisory.affected_binary)
-            .and_then(|s| s.commands.first())
-            .map(|c| c.cli_command)
+        let cmd = crate::registry::primary_command(&m.advisory.affected_binary)
             .unwrap_or(&m.advisory.affected_binary);
 
         eprintln!(
@@ -153,12 +153,15 @@ mod tests {
                 version: Version::parse(version).unwrap(),
                 installed_at: Utc::now(),
                 asset_name: "test.tar.gz".to_string(),
+                file: None,
+                hashes: None,
+                recovered: false,
+                extra: serde_json::Map::new(),
             },
         );
         AppState {
             binaries,
-            last_update_check: None,
-            update_check_interval_hours: 24,
+            ..AppState::default()
         }
     }
 
@@ -0,0 +1,350 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::ownership;
+use crate::platform;
+
+/// Index of cached archives, stored next to the blobs.
+const INDEX_FILE: &str = "index.json";
+
+/// A cached release archive. Several entries may share a blob when the same
+/// file was published under more than one tag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// GitHub repository (e.g., "iii-hq/console")
+    pub repo: String,
+    /// Release tag the archive was downloaded from
+    pub tag: String,
+    /// Release asset file name
+    pub asset: String,
+    /// SHA256 of the archive, lowercase hex; names the blob
+    pub sha256: String,
+    pub size: u64,
+    pub added_at: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+}
+
+impl CacheEntry {
+    fn is_for(&self, repo: &str, tag: &str, asset: &str) -> bool {
+        self.repo == repo && self.tag == tag && self.asset == asset
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    #[serde(default)]
+    entries: Vec<CacheEntry>,
+}
+
+/// Content-addressed cache of downloaded release archives.
+///
+/// Layout: `<dir>/sha256/<hash>` for archives and `<dir>/index.json`
+/// mapping repo/tag/asset to hashes. The cache only saves downloads, so a
+/// missing or unreadable index is treated as empty.
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    index: CacheIndex,
+}
+
+impl Cache {
+    /// Open the cache in `dir`.
+    pub fn open(dir: &Path) -> Self {
+        let index = std::fs::read_to_string(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            dir: dir.to_path_buf(),
+            index,
+        }
+    }
+
+    /// Open the cache in cache_dir().
+    pub fn open_default() -> Self {
+        Self::open(&platform::cache_dir())
+    }
+
+    /// Cached archives, least recently used first.
+    pub fn entries(&self) -> Vec<&CacheEntry> {
+        let mut entries: Vec<&CacheEntry> = self.index.entries.iter().collect();
+        entries.sort_by_key(|e| e.last_used);
+        entries
+    }
+
+    /// Path of the blob holding the archive with this hash.
+    pub fn blob_path(&self, sha256: &str) -> PathBuf {
+        self.dir.join("sha256").join(sha256)
+    }
+
+    /// Total size of the cached blobs, counting shared blobs once.
+    pub fn size(&self) -> u64 {
+        let mut seen = HashSet::new();
+        self.index
+            .entries
+            .iter()
+            .filter(|e| seen.insert(e.sha256.as_str()))
+            .map(|e| e.size)
+            .sum()
+    }
+
+    /// Find a cached archive by its hash when it is known, otherwise by
+    /// repo/tag/asset. The blob is re-hashed, and dropped if it no longer
+    /// matches its name. Returns the blob path and hash, and marks the
+    /// entry as used.
+    pub fn find(
+        &mut self,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+        sha256: Option<&str>,
+    ) -> Option<(PathBuf, String)> {
+        let sha256 = match sha256 {
+            Some(sha256) => sha256.to_lowercase(),
+            None => self
+                .index
+                .entries
+                .iter()
+                .find(|e| e.is_for(repo, tag, asset))?
+                .sha256
+                .clone(),
+        };
+        if !self.index.entries.iter().any(|e| e.sha256 == sha256) {
+            return None;
+        }
+
+        let blob = self.blob_path(&sha256);
+        if !ownership::hash_file(&blob).is_ok_and(|actual| actual == sha256) {
+            self.index.entries.retain(|e| e.sha256 != sha256);
+            let _ = std::fs::remove_file(&blob);
+            return None;
+        }
+
+        let now = Utc::now();
+        let mut found = false;
+        for entry in self.index.entries.iter_mut().filter(|e| e.sha256 == sha256) {
+            entry.last_used = now;
+            found |= entry.is_for(repo, tag, asset);
+        }
+        // Reached by hash under another name: index it under this one too
+        if !found {
+            let size = std::fs::metadata(&blob).map(|m| m.len()).unwrap_or(0);
+            self.index.entries.push(new_entry(repo, tag, asset, &sha256, size, now));
+        }
+        Some((blob, sha256))
+    }
+
+    /// Move a verified archive into the cache. Returns the blob path.
+    pub fn insert(
+        &mut self,
+        repo: &str,
+        tag: &str,
+        asset: &str,
+        sha256: &str,
+        file: &Path,
+    ) -> std::io::Result<PathBuf> {
+        let blob = self.blob_path(sha256);
+        if let Some(parent) = blob.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(file, &blob)?;
+        let size = std::fs::metadata(&blob)?.len();
+
+        self.forget(repo, tag, asset);
+        self.index
+            .entries
+            .push(new_entry(repo, tag, asset, sha256, size, Utc::now()));
+        Ok(blob)
+    }
+
+    /// Drop the entry for repo/tag/asset, e.g. when the release asset was
+    /// replaced. The blob is removed unless another entry shares it.
+    pub fn forget(&mut self, repo: &str, tag: &str, asset: &str) {
+        let removed: Vec<CacheEntry> = self
+            .index
+            .entries
+            .extract_if(.., |e| e.is_for(repo, tag, asset))
+            .collect();
+        for entry in removed {
+            self.remove_unreferenced_blob(&entry.sha256);
+        }
+    }
+
+    /// Remove least recently used archives until the cache fits in `limit`
+    /// bytes. Returns the removed entries.
+    pub fn evict(&mut self, limit: u64) -> Vec<CacheEntry> {
+        let mut evicted = Vec::new();
+        self.index.entries.sort_by_key(|e| e.last_used);
+        while self.size() > limit && !self.index.entries.is_empty() {
+            let entry = self.index.entries.remove(0);
+            self.remove_unreferenced_blob(&entry.sha256);
+            evicted.push(entry);
+        }
+        evicted
+    }
+
+    /// Remove every cached archive. Returns the number of bytes freed.
+    pub fn clear(&mut self) -> std::io::Result<u64> {
+        let freed = self.size();
+        self.index.entries.clear();
+        match std::fs::remove_dir_all(self.dir.join("sha256")) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.save()?;
+        Ok(freed)
+    }
+
+    /// Write the index.
+    pub fn save(&self) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(INDEX_FILE);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(&self.index)?)?;
+        std::fs::rename(&temp_path, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })
+    }
+
+    fn remove_unreferenced_blob(&self, sha256: &str) {
+        if !self.index.entries.iter().any(|e| e.sha256 == sha256) {
+            let _ = std::fs::remove_file(self.blob_path(sha256));
+        }
+    }
+}
+
+fn new_entry(
+    repo: &str,
+    tag: &str,
+    asset: &str,
+    sha256: &str,
+    size: u64,
+    now: DateTime<Utc>,
+) -> CacheEntry {
+    CacheEntry {
+        repo: repo.to_string(),
+        tag: tag.to_string(),
+        asset: asset.to_string(),
+        sha256: sha256.to_string(),
+        size,
+        added_at: now,
+        last_used: now,
+    }
+}
+
+/// Print cached archives, least recently used first.
+pub fn print_entries(entries: &[&CacheEntry]) {
+    for entry in entries {
+        eprintln!(
+            "  {:<24}  {:<10}  {:<44}  {:>9}  {}",
+            entry.repo,
+            entry.tag,
+            entry.asset,
+            format_size(entry.size),
+            format!(
+                "used {}",
+                entry.last_used.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            )
+            .dimmed()
+        );
+    }
+}
+
+/// Human-readable size, e.g. "12.3 MB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(cache: &mut Cache, dir: &Path, tag: &str, contents: &[u8]) -> String {
+        let file = dir.join(format!("{}.part", tag));
+        std::fs::write(&file, contents).unwrap();
+        let sha256 = ownership::hash_file(&file).unwrap();
+        cache
+            .insert("iii-hq/iii", tag, "iii.tar.gz", &sha256, &file)
+            .unwrap();
+        sha256
+    }
+
+    #[test]
+    fn test_find_by_key_and_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = Cache::open(&dir.path().join("cache"));
+        let sha256 = add(&mut cache, dir.path(), "v0.4.2", b"archive");
+        cache.save().unwrap();
+
+        let mut cache = Cache::open(&dir.path().join("cache"));
+        let (blob, found) = cache
+            .find("iii-hq/iii", "v0.4.2", "iii.tar.gz", None)
+            .unwrap();
+        assert_eq!(found, sha256);
+        assert_eq!(std::fs::read(blob).unwrap(), b"archive");
+        assert!(cache.find("iii-hq/iii", "v0.4.3", "iii.tar.gz", None).is_none());
+
+        // The same archive re-tagged is found by hash and indexed again
+        assert!(cache
+            .find("iii-hq/iii", "0.4.2", "iii.tar.gz", Some(&sha256.to_uppercase()))
+            .is_some());
+        assert_eq!(cache.entries().len(), 2);
+        assert_eq!(cache.size(), 7);
+    }
+
+    #[test]
+    fn test_corrupt_blob_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = Cache::open(&dir.path().join("cache"));
+        let sha256 = add(&mut cache, dir.path(), "v0.4.2", b"archive");
+
+        std::fs::write(cache.blob_path(&sha256), b"tampered").unwrap();
+        assert!(cache.find("iii-hq/iii", "v0.4.2", "iii.tar.gz", None).is_none());
+        assert!(cache.entries().is_empty());
+        assert!(!cache.blob_path(&sha256).exists());
+    }
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = Cache::open(&dir.path().join("cache"));
+        let old = add(&mut cache, dir.path(), "v0.4.1", &[1; 100]);
+        let new = add(&mut cache, dir.path(), "v0.4.2", &[2; 100]);
+        // Using the older archive makes the newer one least recently used
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        cache.find("iii-hq/iii", "v0.4.1", "iii.tar.gz", None).unwrap();
+
+        let evicted = cache.evict(150);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].tag, "v0.4.2");
+        assert!(cache.blob_path(&old).exists());
+        assert!(!cache.blob_path(&new).exists());
+
+        assert_eq!(cache.clear().unwrap(), 100);
+        assert!(cache.entries().is_empty());
+        assert!(!cache.blob_path(&old).exists());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(250 * 1024 * 1024), "250.0 MB");
+    }
+}
@@ -1,4 +1,12 @@
-use clap::{Parser, Subcommand};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::sync::OnceLock;
+
+use clap::{Arg, ArgMatches, Command, CommandFactory, Parser, Subcommand};
+
+use crate::config::Scope;
+use crate::plugin::Plugin;
+use crate::registry::{self, CommandMapping};
 
 #[derive(Parser)]
 #[command(
@@ -17,44 +25,16 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Launch the iii web console
-    #[command(
-        trailing_var_arg = true,
-        allow_hyphen_values = true,
-        disable_help_flag = true,
-    )]
-    Console {
-        /// Arguments passed through to the binary
-        #[arg(num_args = 0..)]
-        args: Vec<String>,
-    },
-
-    /// Create a new iii project from a template
-    #[command(
-        trailing_var_arg = true,
-        allow_hyphen_values = true,
-        disable_help_flag = true,
-    )]
-    Create {
-        /// Arguments passed through to the binary
-        #[arg(num_args = 0..)]
-        args: Vec<String>,
-    },
-
-    /// Manage SDKs powered by Motia
-    #[command(subcommand)]
-    Sdk(SdkCommands),
+    /// Install a managed binary, optionally pinned to a version
+    Install {
+        /// Command or binary to install, with an optional version
+        /// (e.g., "start@0.4.2", "iii-console@0.3.1", "console").
+        #[arg(name = "command")]
+        target: String,
 
-    /// Start the iii process communication engine
-    #[command(
-        trailing_var_arg = true,
-        allow_hyphen_values = true,
-        disable_help_flag = true,
-    )]
-    Start {
-        /// Arguments passed through to the binary
-        #[arg(num_args = 0..)]
-        args: Vec<String>,
+        /// Overwrite an existing binary that iii-cli did not install, or that changed since
+        #[arg(long)]
+        force: bool,
     },
 
     /// Update iii-cli and managed binaries to their latest versions
@@ -64,65 +44,492 @@ pub enum Commands {
         /// If omitted, updates iii-cli and all installed binaries.
         #[arg(name = "command")]
         target: Option<String>,
+
+        /// Install this exact version instead of the latest (requires a command)
+        #[arg(long, requires = "command")]
+        version: Option<String>,
+
+        /// Overwrite binaries that iii-cli did not install, or that changed since
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove a managed binary and all of its installed versions
+    Uninstall {
+        /// Command or binary to remove (e.g., "console", "iii")
+        #[arg(name = "command")]
+        target: String,
+
+        /// Remove the binary even if it changed since iii-cli installed it
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Resolve every tool and write iii-toolchain.lock with archive hashes
+    Lock,
+
+    /// Install the tools pinned for the current project
+    Sync {
+        /// Install exactly the archives in iii-toolchain.lock and fail on any hash difference
+        #[arg(long)]
+        locked: bool,
+    },
+
+    /// Switch the active version of a binary to one that is already installed
+    Use {
+        /// Command or binary with the version to activate (e.g., "iii@0.4.1")
+        #[arg(name = "command")]
+        target: String,
+
+        /// Switch even if the active binary was not installed by iii-cli, or changed since
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Restore the version a binary had before its last update or switch
+    Rollback {
+        /// Command or binary to roll back (e.g., "console")
+        #[arg(name = "command")]
+        target: String,
+
+        /// Roll back even if the active binary was not installed by iii-cli, or changed since
+        #[arg(long)]
+        force: bool,
     },
 
     /// Show installed binaries and their versions
     List,
+
+    /// Show past installs, updates, rollbacks and uninstalls
+    History {
+        /// Command or binary to show (e.g., "console"). Shows all if omitted.
+        #[arg(name = "command")]
+        target: Option<String>,
+    },
+
+    /// Re-hash installed binaries and report any that were modified or corrupted
+    Verify {
+        /// Command or binary to verify (e.g., "console"). Verifies all if omitted.
+        #[arg(name = "command")]
+        target: Option<String>,
+
+        /// Download damaged binaries again
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Check for problems that break installs, with a fix for each
+    Doctor {
+        /// Print findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Read and write iii-cli settings
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Inspect and clear the cache of downloaded release archives
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
 }
 
-/// SDK subcommands
 #[derive(Subcommand)]
-pub enum SdkCommands {
-    /// Motia SDK tools
-    #[command(
-        trailing_var_arg = true,
-        allow_hyphen_values = true,
-        disable_help_flag = true,
-    )]
-    Motia {
-        /// Arguments passed through to the binary
-        #[arg(num_args = 0..)]
-        args: Vec<String>,
+pub enum ConfigCommands {
+    /// Print the effective value of a setting
+    Get {
+        /// Setting key (e.g., "network.timeout_secs", "alias.dev")
+        key: String,
+
+        /// Show which file or environment variable the value came from
+        #[arg(long)]
+        show_origin: bool,
+    },
+
+    /// Write a setting to a config file (the user file unless --system or --project)
+    Set {
+        /// Setting key (e.g., "network.timeout_secs", "alias.dev")
+        key: String,
+
+        /// New value
+        value: String,
+
+        /// Write the machine-wide config file
+        #[arg(long, conflicts_with = "project")]
+        system: bool,
+
+        /// Write the project's .iii-cli.toml
+        #[arg(long)]
+        project: bool,
+    },
+
+    /// Print every effective setting
+    List {
+        /// Show which file or environment variable each value came from
+        #[arg(long)]
+        show_origin: bool,
     },
 }
 
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Show cached archives, least recently used first
+    List,
+
+    /// Remove every cached archive
+    Clean,
+
+    /// Print the total size of the cache and its limit
+    Size,
+}
+
+/// Build the full command tree: the built-in management commands from `Cli`
+/// plus one passthrough subcommand per registry command, nested under its
+/// namespace (e.g., "sdk motia") when it has one, and one per plugin.
+pub fn build_command(plugins: &[Plugin]) -> Command {
+    let mut command = Cli::command();
+
+    for mapping in registry::command_mappings().filter(|m| m.namespace.is_none()) {
+        command = command.subcommand(passthrough_command(mapping));
+    }
+
+    for namespace in registry::namespaces() {
+        let mut group = Command::new(namespace)
+            .about(registry::namespace_about(namespace))
+            .display_order(0)
+            .subcommand_required(true)
+            .arg_required_else_help(true);
+        for mapping in registry::command_mappings().filter(|m| m.namespace == Some(namespace)) {
+            group = group.subcommand(passthrough_command(mapping));
+        }
+        command = command.subcommand(group);
+    }
+
+    // Built-in and registry commands take precedence over plugins
+    for plugin in plugins.iter().filter(|p| !is_reserved(&p.name)) {
+        command = command.subcommand(
+            Command::new(plugin.name.clone())
+                .about(format!("Plugin: {}", plugin.path.display()))
+                // List plugins after every other command
+                .display_order(usize::MAX)
+                .disable_help_flag(true)
+                .arg(passthrough_args()),
+        );
+    }
+
+    command
+}
+
+/// Whether a name is taken by a built-in command or one of its aliases.
+pub fn is_builtin(name: &str) -> bool {
+    static BUILTIN: OnceLock<HashSet<String>> = OnceLock::new();
+    BUILTIN
+        .get_or_init(|| {
+            let mut names: HashSet<String> = Cli::command()
+                .get_subcommands()
+                .flat_map(|c| std::iter::once(c.get_name()).chain(c.get_all_aliases()))
+                .map(str::to_string)
+                .collect();
+            names.insert("help".to_string());
+            names
+        })
+        .contains(name)
+}
+
+/// Whether a subcommand name belongs to a built-in or registry command. The
+/// registry names are collected on first use, so the registry must already
+/// be initialized.
+pub fn is_reserved(name: &str) -> bool {
+    static REGISTRY_NAMES: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    is_builtin(name)
+        || REGISTRY_NAMES
+            .get_or_init(|| {
+                registry::command_mappings()
+                    .filter(|m| m.namespace.is_none())
+                    .map(|m| m.cli_command)
+                    .chain(registry::namespaces())
+                    .collect()
+            })
+            .contains(name)
+}
+
+/// Whether parsing these arguments needs the plugins on disk: the
+/// subcommand may be a plugin, or it is `list` or help, which show them.
+/// Built-in and registry commands skip scanning PATH.
+pub fn needs_plugins(args: &[OsString]) -> bool {
+    let subcommand = args
+        .iter()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy())
+        .find(|arg| !arg.starts_with('-'));
+    match subcommand {
+        Some(name) => matches!(name.as_ref(), "list" | "help") || !is_reserved(&name),
+        None => true,
+    }
+}
+
+/// A subcommand that forwards every argument, including flags and `--help`,
+/// to the managed binary.
+fn passthrough_command(mapping: &CommandMapping) -> Command {
+    Command::new(mapping.cli_command)
+        .about(mapping.about)
+        // List tool commands ahead of the built-in management commands
+        .display_order(0)
+        .disable_help_flag(true)
+        .arg(passthrough_args())
+}
+
+fn passthrough_args() -> Arg {
+    Arg::new("args")
+        .help("Arguments passed through to the binary")
+        .num_args(0..)
+        .trailing_var_arg(true)
+        .allow_hyphen_values(true)
+}
+
+/// If the parsed arguments select a registry command, return its mapping
+/// and passthrough args. Returns None for built-in commands.
+pub fn dispatch_target(matches: &ArgMatches) -> Option<(&'static CommandMapping, Vec<String>)> {
+    let (name, sub_matches) = matches.subcommand()?;
+
+    let (mapping, sub_matches) = match registry::command_mappings()
+        .find(|m| m.namespace.is_none() && m.cli_command == name)
+    {
+        Some(mapping) => (mapping, sub_matches),
+        None => {
+            let (command, nested) = sub_matches.subcommand()?;
+            let mapping = registry::command_mappings()
+                .find(|m| m.namespace == Some(name) && m.cli_command == command)?;
+            (mapping, nested)
+        }
+    };
+
+    Some((mapping, passthrough_values(sub_matches)))
+}
+
+/// If the parsed arguments select a plugin, return it with its passthrough args.
+pub fn plugin_target<'a>(
+    matches: &ArgMatches,
+    plugins: &'a [Plugin],
+) -> Option<(&'a Plugin, Vec<String>)> {
+    let (name, sub_matches) = matches.subcommand()?;
+    if is_reserved(name) {
+        return None;
+    }
+    let plugin = plugins.iter().find(|p| p.name == name)?;
+    Some((plugin, passthrough_values(sub_matches)))
+}
+
+fn passthrough_values(matches: &ArgMatches) -> Vec<String> {
+    matches
+        .get_many::<String>("args")
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default()
+}
+
 /// Extract the command name and passthrough args from a parsed Commands value.
 pub fn extract_command_info(cmd: &Commands) -> CommandInfo<'_> {
     match cmd {
-        Commands::Console { args } => CommandInfo::Dispatch {
-            command: "console",
-            args,
+        Commands::Install { target, force } => CommandInfo::Install {
+            target,
+            force: *force,
+        },
+        Commands::Update {
+            target,
+            version,
+            force,
+        } => CommandInfo::Update {
+            target: target.as_deref(),
+            version: version.as_deref(),
+            force: *force,
         },
-        Commands::Create { args } => CommandInfo::Dispatch {
-            command: "create",
-            args,
+        Commands::Uninstall { target, force } => CommandInfo::Uninstall {
+            target,
+            force: *force,
         },
-        Commands::Sdk(sdk_cmd) => match sdk_cmd {
-            SdkCommands::Motia { args } => CommandInfo::Dispatch {
-                command: "motia",
-                args,
-            },
+        Commands::Lock => CommandInfo::Lock,
+        Commands::Sync { locked } => CommandInfo::Sync { locked: *locked },
+        Commands::Use { target, force } => CommandInfo::Use {
+            target,
+            force: *force,
         },
-        Commands::Start { args } => CommandInfo::Dispatch {
-            command: "start",
-            args,
+        Commands::Rollback { target, force } => CommandInfo::Rollback {
+            target,
+            force: *force,
         },
-        Commands::Update { target } => CommandInfo::Update {
+        Commands::List => CommandInfo::List,
+        Commands::History { target } => CommandInfo::History {
             target: target.as_deref(),
         },
-        Commands::List => CommandInfo::List,
+        Commands::Verify { target, repair } => CommandInfo::Verify {
+            target: target.as_deref(),
+            repair: *repair,
+        },
+        Commands::Doctor { json } => CommandInfo::Doctor { json: *json },
+        Commands::Config { command } => match command {
+            ConfigCommands::Get { key, show_origin } => CommandInfo::ConfigGet {
+                key,
+                show_origin: *show_origin,
+            },
+            ConfigCommands::Set {
+                key,
+                value,
+                system,
+                project,
+            } => CommandInfo::ConfigSet {
+                key,
+                value,
+                scope: if *system {
+                    Scope::System
+                } else if *project {
+                    Scope::Project
+                } else {
+                    Scope::User
+                },
+            },
+            ConfigCommands::List { show_origin } => CommandInfo::ConfigList {
+                show_origin: *show_origin,
+            },
+        },
+        Commands::Cache { command } => match command {
+            CacheCommands::List => CommandInfo::CacheList,
+            CacheCommands::Clean => CommandInfo::CacheClean,
+            CacheCommands::Size => CommandInfo::CacheSize,
+        },
     }
 }
 
 /// Parsed command information for the main dispatcher.
 pub enum CommandInfo<'a> {
-    /// Dispatch to a managed binary with passthrough args
-    Dispatch {
-        command: &'static str,
-        args: &'a [String],
-    },
+    /// Install command
+    Install { target: &'a str, force: bool },
     /// Update command
-    Update { target: Option<&'a str> },
+    Update {
+        target: Option<&'a str>,
+        version: Option<&'a str>,
+        force: bool,
+    },
+    /// Uninstall command
+    Uninstall { target: &'a str, force: bool },
+    /// Write the project lockfile
+    Lock,
+    /// Install project-pinned tools
+    Sync { locked: bool },
+    /// Switch active version
+    Use { target: &'a str, force: bool },
+    /// Restore the previous version
+    Rollback { target: &'a str, force: bool },
     /// List installed binaries
     List,
+    /// Show the install history
+    History { target: Option<&'a str> },
+    /// Verify installed binaries
+    Verify {
+        target: Option<&'a str>,
+        repair: bool,
+    },
+    /// Diagnose the installation
+    Doctor { json: bool },
+    /// Print one config value
+    ConfigGet { key: &'a str, show_origin: bool },
+    /// Write one config value
+    ConfigSet {
+        key: &'a str,
+        value: &'a str,
+        scope: Scope,
+    },
+    /// Print all config values
+    ConfigList { show_origin: bool },
+    /// List cached archives
+    CacheList,
+    /// Clear the download cache
+    CacheClean,
+    /// Print the download cache size
+    CacheSize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::FromArgMatches;
+
+    fn parse(args: &[&str]) -> ArgMatches {
+        build_command(&[]).try_get_matches_from(args).unwrap()
+    }
+
+    fn plugins() -> Vec<Plugin> {
+        ["deploy", "list"]
+            .iter()
+            .map(|name| Plugin {
+                name: name.to_string(),
+                path: format!("/opt/bin/iii-cli-{}", name).into(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_command_tree_is_valid() {
+        build_command(&plugins()).debug_assert();
+    }
+
+    #[test]
+    fn test_dispatch_top_level_command() {
+        let matches = parse(&["iii-cli", "console", "--port", "3000"]);
+        let (mapping, args) = dispatch_target(&matches).unwrap();
+        assert_eq!(mapping.path(), "console");
+        assert_eq!(args, vec!["--port", "3000"]);
+    }
+
+    #[test]
+    fn test_dispatch_namespaced_command_passes_help_through() {
+        let matches = parse(&["iii-cli", "sdk", "motia", "--help"]);
+        let (mapping, args) = dispatch_target(&matches).unwrap();
+        assert_eq!(mapping.path(), "sdk motia");
+        assert_eq!(args, vec!["--help"]);
+    }
+
+    #[test]
+    fn test_builtin_commands_are_not_dispatched() {
+        let matches = parse(&["iii-cli", "update", "console"]);
+        assert!(dispatch_target(&matches).is_none());
+        assert!(Cli::from_arg_matches(&matches).is_ok());
+    }
+
+    #[test]
+    fn test_plugin_dispatch() {
+        let plugins = plugins();
+        let matches = build_command(&plugins)
+            .try_get_matches_from(["iii-cli", "deploy", "--env", "staging"])
+            .unwrap();
+        assert!(dispatch_target(&matches).is_none());
+        let (plugin, args) = plugin_target(&matches, &plugins).unwrap();
+        assert_eq!(plugin.name, "deploy");
+        assert_eq!(args, vec!["--env", "staging"]);
+    }
+
+    #[test]
+    fn test_needs_plugins() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert!(!needs_plugins(&args(&["iii-cli", "start", "--port", "3000"])));
+        assert!(!needs_plugins(&args(&["iii-cli", "--no-update-check", "update"])));
+        assert!(!needs_plugins(&args(&["iii-cli", "sdk", "motia"])));
+        assert!(needs_plugins(&args(&["iii-cli", "deploy"])));
+        assert!(needs_plugins(&args(&["iii-cli", "list"])));
+        assert!(needs_plugins(&args(&["iii-cli", "help"])));
+        assert!(needs_plugins(&args(&["iii-cli", "--help"])));
+    }
+
+    #[test]
+    fn test_plugin_cannot_shadow_builtin() {
+        let plugins = plugins();
+        let matches = build_command(&plugins)
+            .try_get_matches_from(["iii-cli", "list"])
+            .unwrap();
+        assert!(plugin_target(&matches, &plugins).is_none());
+        assert!(Cli::from_arg_matches(&matches).is_ok());
+    }
 }
@@ -0,0 +1,967 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use colored::Colorize;
+use toml::Value;
+
+use crate::error::ConfigError;
+use crate::platform;
+
+/// File name of the project-level config file, searched upward from the cwd.
+pub const PROJECT_CONFIG_FILE: &str = ".iii-cli.toml";
+
+/// Prefix of alias keys (`[alias]` table entries).
+const ALIAS_PREFIX: &str = "alias.";
+
+/// The type of value a setting accepts.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    String,
+    /// Non-negative integer
+    Integer,
+    /// One of a fixed set of strings
+    Choice(&'static [&'static str]),
+}
+
+/// A known configuration setting.
+///
+/// Settings are written as `<section>.<name>` keys, e.g. `network.timeout_secs`
+/// is `timeout_secs` in the `[network]` table.
+pub struct Setting {
+    pub key: &'static str,
+    /// Environment variables overriding the setting, highest priority first
+    pub env: &'static [&'static str],
+    kind: Kind,
+    default: Option<&'static str>,
+    /// Masked in `config list`
+    secret: bool,
+    /// Whether a project `.iii-cli.toml` may set it. Settings that hold
+    /// credentials, choose where trusted content comes from, or weaken
+    /// checks are read only from system, user and env layers, since any
+    /// cloned repository can ship a project file.
+    project: bool,
+}
+
+/// Every setting iii-cli reads, besides aliases.
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "github.token",
+        env: &["III_GITHUB_TOKEN", "GITHUB_TOKEN"],
+        kind: Kind::String,
+        default: None,
+        secret: true,
+        project: false,
+    },
+    Setting {
+        key: "network.timeout_secs",
+        env: &["III_NETWORK_TIMEOUT_SECS"],
+        kind: Kind::Integer,
+        default: Some("30"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "network.retries",
+        env: &["III_NETWORK_RETRIES"],
+        kind: Kind::Integer,
+        default: Some("3"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "network.retry_base_delay_ms",
+        env: &["III_NETWORK_RETRY_BASE_DELAY_MS"],
+        kind: Kind::Integer,
+        default: Some("500"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "network.retry_max_delay_secs",
+        env: &["III_NETWORK_RETRY_MAX_DELAY_SECS"],
+        kind: Kind::Integer,
+        default: Some("30"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "download.connect_timeout_secs",
+        env: &["III_DOWNLOAD_CONNECT_TIMEOUT_SECS"],
+        kind: Kind::Integer,
+        default: Some("10"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "download.read_timeout_secs",
+        env: &["III_DOWNLOAD_READ_TIMEOUT_SECS"],
+        kind: Kind::Integer,
+        default: Some("30"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "download.timeout_secs",
+        env: &["III_DOWNLOAD_TIMEOUT_SECS"],
+        kind: Kind::Integer,
+        default: Some("0"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "cache.max_size_mb",
+        env: &["III_CACHE_MAX_SIZE_MB"],
+        kind: Kind::Integer,
+        default: Some("1024"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "advisories.url",
+        env: &["III_ADVISORIES_URL"],
+        kind: Kind::String,
+        default: Some(crate::advisory::DEFAULT_ADVISORIES_URL),
+        secret: false,
+        project: false,
+    },
+    Setting {
+        key: "registry.url",
+        env: &["III_REGISTRY_URL"],
+        kind: Kind::String,
+        default: Some(crate::remote_registry::DEFAULT_REGISTRY_URL),
+        secret: false,
+        project: false,
+    },
+    Setting {
+        key: "update.check_timeout_ms",
+        env: &["III_UPDATE_CHECK_TIMEOUT_MS"],
+        kind: Kind::Integer,
+        default: Some("500"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "update.check_interval_hours",
+        env: &["III_UPDATE_CHECK_INTERVAL_HOURS"],
+        kind: Kind::Integer,
+        default: Some("24"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "lock.timeout_secs",
+        env: &["III_LOCK_TIMEOUT_SECS"],
+        kind: Kind::Integer,
+        default: Some("600"),
+        secret: false,
+        project: true,
+    },
+    Setting {
+        key: "exec.tamper_check",
+        env: &["III_EXEC_TAMPER_CHECK"],
+        kind: Kind::Choice(&["off", "warn", "strict"]),
+        default: Some("warn"),
+        secret: false,
+        project: false,
+    },
+];
+
+fn setting(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.key == key)
+}
+
+/// Where an effective value came from, lowest priority first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    Env(&'static str),
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System(path) => write!(f, "system:{}", path.display()),
+            Self::User(path) => write!(f, "user:{}", path.display()),
+            Self::Project(path) => write!(f, "project:{}", path.display()),
+            Self::Env(name) => write!(f, "env:{}", name),
+        }
+    }
+}
+
+/// Which config file `config set` writes to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    System,
+    User,
+    Project,
+}
+
+/// What to do when a managed binary changed since iii-cli installed it
+/// (`exec.tamper_check`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TamperCheck {
+    /// Run it without checking
+    Off,
+    /// Print a warning and run it
+    Warn,
+    /// Refuse to run it
+    Strict,
+}
+
+/// An effective config value and the layer it came from.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub value: Value,
+    pub origin: Origin,
+}
+
+impl Entry {
+    /// The value as shown by `config get` and `config list`: strings are
+    /// printed raw, anything else in TOML syntax.
+    pub fn display_value(&self) -> String {
+        match &self.value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Effective configuration, merged from defaults, the system, user and
+/// project config files, and environment variables (in increasing priority).
+#[derive(Debug)]
+pub struct Config {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Default for Config {
+    /// Built-in defaults only.
+    fn default() -> Self {
+        let mut entries = BTreeMap::new();
+        for setting in SETTINGS {
+            if let Some(raw) = setting.default {
+                let value = parse_value(setting.kind, raw).expect("valid built-in default");
+                entries.insert(
+                    setting.key.to_string(),
+                    Entry {
+                        value,
+                        origin: Origin::Default,
+                    },
+                );
+            }
+        }
+        Self { entries }
+    }
+}
+
+impl Config {
+    /// Load every layer for a process started in `cwd`. A layer that cannot
+    /// be read or holds an invalid value is skipped as a whole, and its error
+    /// returned, so the other layers still apply.
+    pub fn load(cwd: &Path) -> (Self, Vec<ConfigError>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+
+        let system = platform::system_config_path();
+        let user = platform::user_config_path();
+        let mut files = vec![
+            (system.clone(), Origin::System(system)),
+            (user.clone(), Origin::User(user)),
+        ];
+        if let Some(project) = find_project_config(cwd) {
+            files.push((project.clone(), Origin::Project(project)));
+        }
+        for (path, origin) in files {
+            if let Err(e) = config.merge_file(&path, origin) {
+                errors.push(e);
+            }
+        }
+        if let Err(e) = config.merge_env(|name| std::env::var(name).ok()) {
+            errors.push(e);
+        }
+
+        (config, errors)
+    }
+
+    /// Merge a config file over the current values. A missing file is skipped.
+    fn merge_file(&mut self, path: &Path, origin: Origin) -> Result<(), ConfigError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(ConfigError::Read {
+                    path: path.display().to_string(),
+                    source: e,
+                })
+            }
+        };
+        self.merge_str(&content, origin)
+    }
+
+    /// Merge config file contents over the current values.
+    ///
+    /// Unknown keys are ignored so that a file written for a newer iii-cli
+    /// still loads.
+    fn merge_str(&mut self, content: &str, origin: Origin) -> Result<(), ConfigError> {
+        let table: toml::Table = toml::from_str(content).map_err(|e| ConfigError::Parse {
+            origin: origin.to_string(),
+            message: e.to_string(),
+        })?;
+
+        // Validate the whole layer before applying any of it
+        let mut layer = Vec::new();
+        for (section, item) in table {
+            let Value::Table(values) = item else {
+                continue;
+            };
+            for (name, value) in values {
+                let key = format!("{}.{}", section, name);
+                let valid = if section == "alias" {
+                    validate_alias(&value)
+                } else if let Some(setting) = setting(&key) {
+                    if matches!(origin, Origin::Project(_)) && !setting.project {
+                        return Err(ConfigError::NotAllowedInProject {
+                            key,
+                            origin: origin.to_string(),
+                        });
+                    }
+                    validate_value(setting.kind, &value)
+                } else {
+                    continue;
+                };
+                valid.map_err(|reason| ConfigError::InvalidValue {
+                    key: key.clone(),
+                    origin: origin.to_string(),
+                    reason,
+                })?;
+                layer.push((key, value));
+            }
+        }
+        for (key, value) in layer {
+            self.entries.insert(
+                key,
+                Entry {
+                    value,
+                    origin: origin.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Merge environment variable overrides, read through `var`.
+    fn merge_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let mut layer = Vec::new();
+        for setting in SETTINGS {
+            let Some((name, raw)) = setting
+                .env
+                .iter()
+                .find_map(|name| var(name).map(|raw| (*name, raw)))
+            else {
+                continue;
+            };
+            let value = parse_value(setting.kind, &raw).map_err(|reason| {
+                ConfigError::InvalidValue {
+                    key: setting.key.to_string(),
+                    origin: Origin::Env(name).to_string(),
+                    reason,
+                }
+            })?;
+            layer.push((setting.key, name, value));
+        }
+        for (key, name, value) in layer {
+            self.entries.insert(
+                key.to_string(),
+                Entry {
+                    value,
+                    origin: Origin::Env(name),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Look up the effective value of a key.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.get(key)
+    }
+
+    /// All effective values, sorted by key.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Entry)> {
+        self.entries.iter().map(|(k, e)| (k.as_str(), e))
+    }
+
+    /// Whether a key holds a secret that should not be printed in listings.
+    pub fn is_secret(key: &str) -> bool {
+        setting(key).is_some_and(|s| s.secret)
+    }
+
+    fn string(&self, key: &str) -> Option<String> {
+        self.get(key)
+            .and_then(|e| e.value.as_str())
+            .map(str::to_string)
+    }
+
+    fn integer(&self, key: &str) -> u64 {
+        self.get(key)
+            .and_then(|e| e.value.as_integer())
+            .or_else(|| setting(key)?.default?.parse().ok())
+            .unwrap_or_default() as u64
+    }
+
+    /// GitHub token for higher API rate limits.
+    pub fn github_token(&self) -> Option<String> {
+        self.string("github.token").filter(|t| !t.is_empty())
+    }
+
+    /// Total timeout for GitHub API and other short HTTP requests.
+    pub fn network_timeout(&self) -> Duration {
+        Duration::from_secs(self.integer("network.timeout_secs"))
+    }
+
+    /// How many times a request that failed transiently is retried.
+    pub fn network_retries(&self) -> u32 {
+        self.integer("network.retries").try_into().unwrap_or(u32::MAX)
+    }
+
+    /// Delay before the first retry; it doubles for each retry after that.
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(self.integer("network.retry_base_delay_ms"))
+    }
+
+    /// Upper bound on the delay between retries, including delays asked
+    /// for with `Retry-After`.
+    pub fn retry_max_delay(&self) -> Duration {
+        Duration::from_secs(self.integer("network.retry_max_delay_secs"))
+    }
+
+    /// How long a download may take to connect.
+    pub fn download_connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.integer("download.connect_timeout_secs"))
+    }
+
+    /// How long a download may go without receiving data.
+    pub fn download_read_timeout(&self) -> Duration {
+        Duration::from_secs(self.integer("download.read_timeout_secs"))
+    }
+
+    /// How long a whole download may take, including retries (None when
+    /// set to 0, the default).
+    pub fn download_timeout(&self) -> Option<Duration> {
+        Some(self.integer("download.timeout_secs"))
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Size limit of the download cache in bytes (0 disables the cache).
+    pub fn cache_max_size(&self) -> u64 {
+        self.integer("cache.max_size_mb").saturating_mul(1024 * 1024)
+    }
+
+    /// URL of the security advisories document.
+    pub fn advisories_url(&self) -> String {
+        self.string("advisories.url")
+            .unwrap_or_else(|| crate::advisory::DEFAULT_ADVISORIES_URL.to_string())
+    }
+
+    /// URL of the remote registry manifest.
+    pub fn registry_url(&self) -> String {
+        self.string("registry.url")
+            .unwrap_or_else(|| crate::remote_registry::DEFAULT_REGISTRY_URL.to_string())
+    }
+
+    /// How long the background update check may delay a command.
+    pub fn update_check_timeout(&self) -> Duration {
+        Duration::from_millis(self.integer("update.check_timeout_ms"))
+    }
+
+    /// Hours between background update checks.
+    pub fn update_check_interval_hours(&self) -> u64 {
+        self.integer("update.check_interval_hours")
+    }
+
+    /// How long to wait for another iii-cli process to release the data
+    /// directory lock.
+    pub fn lock_timeout(&self) -> Duration {
+        Duration::from_secs(self.integer("lock.timeout_secs"))
+    }
+
+    /// How binaries are checked for tampering before they run.
+    pub fn tamper_check(&self) -> TamperCheck {
+        match self.string("exec.tamper_check").as_deref() {
+            Some("off") => TamperCheck::Off,
+            Some("strict") => TamperCheck::Strict,
+            _ => TamperCheck::Warn,
+        }
+    }
+
+    /// The arguments an alias expands to.
+    pub fn alias(&self, name: &str) -> Option<Vec<String>> {
+        let entry = self.get(&format!("{}{}", ALIAS_PREFIX, name))?;
+        match &entry.value {
+            Value::String(line) => Some(line.split_whitespace().map(str::to_string).collect()),
+            Value::Array(args) => args
+                .iter()
+                .map(|a| a.as_str().map(str::to_string))
+                .collect(),
+            _ => None,
+        }
+    }
+
+    /// Expand an alias in the command position of `args` (the first argument
+    /// after the program name that is not a flag).
+    ///
+    /// Aliases never replace built-in or registry commands: `is_reserved`
+    /// identifies those, and a shadowing alias is ignored with a warning.
+    pub fn expand_aliases(
+        &self,
+        args: Vec<OsString>,
+        is_reserved: impl Fn(&str) -> bool,
+    ) -> Vec<OsString> {
+        let Some(position) = args
+            .iter()
+            .skip(1)
+            .position(|arg| !arg.to_string_lossy().starts_with('-'))
+            .map(|i| i + 1)
+        else {
+            return args;
+        };
+        let Some(name) = args[position].to_str() else {
+            return args;
+        };
+        let Some(expansion) = self.alias(name) else {
+            return args;
+        };
+
+        if is_reserved(name) {
+            eprintln!(
+                "{} alias `{}` is ignored because it is shadowed by the built-in `{}` command",
+                "warning:".yellow(),
+                name,
+                name,
+            );
+            return args;
+        }
+
+        let mut expanded = Vec::with_capacity(args.len() + expansion.len());
+        expanded.extend_from_slice(&args[..position]);
+        expanded.extend(expansion.iter().map(OsString::from));
+        expanded.extend_from_slice(&args[position + 1..]);
+        expanded
+    }
+}
+
+/// Path of the config file written for a scope, relative to `cwd` for
+/// project scope (the nearest existing project file, or a new one in `cwd`).
+pub fn scope_path(scope: Scope, cwd: &Path) -> PathBuf {
+    match scope {
+        Scope::System => platform::system_config_path(),
+        Scope::User => platform::user_config_path(),
+        Scope::Project => {
+            find_project_config(cwd).unwrap_or_else(|| cwd.join(PROJECT_CONFIG_FILE))
+        }
+    }
+}
+
+/// Check that `key` may be written to the config file of `scope`.
+pub fn check_scope(scope: Scope, key: &str, path: &Path) -> Result<(), ConfigError> {
+    match setting(key) {
+        Some(setting) if scope == Scope::Project && !setting.project => {
+            Err(ConfigError::NotAllowedInProject {
+                key: key.to_string(),
+                origin: Origin::Project(path.to_path_buf()).to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Set a key in a config file, keeping the rest of the file (including
+/// comments) intact.
+pub fn set(path: &Path, key: &str, raw: &str) -> Result<(), ConfigError> {
+    let (section, name) = key
+        .split_once('.')
+        .filter(|(section, name)| !section.is_empty() && !name.is_empty())
+        .ok_or_else(|| unknown_key(key))?;
+    let kind = if section == "alias" {
+        Kind::String
+    } else {
+        setting(key).ok_or_else(|| unknown_key(key))?.kind
+    };
+    let invalid = |reason: String| ConfigError::InvalidValue {
+        key: key.to_string(),
+        origin: path.display().to_string(),
+        reason,
+    };
+    let value = parse_value(kind, raw).map_err(invalid)?;
+    if section == "alias" {
+        validate_alias(&value).map_err(invalid)?;
+    }
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(ConfigError::Read {
+                path: path.display().to_string(),
+                source: e,
+            })
+        }
+    };
+    let mut doc: toml_edit::DocumentMut = content.parse().map_err(|e: toml_edit::TomlError| {
+        ConfigError::Parse {
+            origin: path.display().to_string(),
+            message: e.to_string(),
+        }
+    })?;
+
+    let item = match value {
+        Value::Integer(i) => toml_edit::value(i),
+        other => toml_edit::value(other.as_str().unwrap_or_default()),
+    };
+    let table = doc
+        .entry(section)
+        .or_insert_with(toml_edit::table)
+        .as_table_mut()
+        .ok_or_else(|| invalid(format!("[{}] is not a table", section)))?;
+    table.insert(name, item);
+
+    let write_err = |e| ConfigError::Write {
+        path: path.display().to_string(),
+        source: e,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(write_err)?;
+    }
+    std::fs::write(path, doc.to_string()).map_err(write_err)
+}
+
+fn unknown_key(key: &str) -> ConfigError {
+    ConfigError::UnknownKey {
+        key: key.to_string(),
+        known: SETTINGS
+            .iter()
+            .map(|s| s.key)
+            .chain(["alias.<name>"])
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// Parse a value given on the command line or in an environment variable.
+fn parse_value(kind: Kind, raw: &str) -> Result<Value, String> {
+    let value = match kind {
+        Kind::String | Kind::Choice(_) => Value::String(raw.to_string()),
+        Kind::Integer => Value::Integer(
+            raw.trim()
+                .parse()
+                .map_err(|_| format!("expected a non-negative integer, got '{}'", raw))?,
+        ),
+    };
+    validate_value(kind, &value)?;
+    Ok(value)
+}
+
+fn validate_value(kind: Kind, value: &Value) -> Result<(), String> {
+    match (kind, value) {
+        (Kind::String, Value::String(_)) => Ok(()),
+        (Kind::Integer, Value::Integer(i)) if *i >= 0 => Ok(()),
+        (Kind::Choice(choices), Value::String(s)) if choices.contains(&s.as_str()) => Ok(()),
+        (Kind::String, _) => Err("expected a string".to_string()),
+        (Kind::Integer, _) => Err("expected a non-negative integer".to_string()),
+        (Kind::Choice(choices), _) => Err(format!("expected one of: {}", choices.join(", "))),
+    }
+}
+
+/// An alias is a whitespace-separated string or a list of arguments, and
+/// must expand to at least one argument.
+fn validate_alias(value: &Value) -> Result<(), String> {
+    let empty = match value {
+        Value::String(line) => line.split_whitespace().next().is_none(),
+        Value::Array(args) => {
+            if !args.iter().all(Value::is_str) {
+                return Err("alias arguments must be strings".to_string());
+            }
+            args.is_empty()
+        }
+        _ => return Err("expected a string or a list of strings".to_string()),
+    };
+    if empty {
+        return Err("alias expands to nothing".to_string());
+    }
+    Ok(())
+}
+
+/// Search `start` and its ancestors for a project config file.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+static CURRENT: OnceLock<Config> = OnceLock::new();
+
+/// Install the effective config for this process. Must be called before
+/// anything reads `current()`; later calls are ignored.
+pub fn init(config: Config) {
+    let _ = CURRENT.set(config);
+}
+
+/// The effective config for this process (built-in defaults if `init` was
+/// never called).
+pub fn current() -> &'static Config {
+    CURRENT.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    fn user() -> Origin {
+        Origin::User(PathBuf::from("config.toml"))
+    }
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config
+            .merge_str(
+                r#"
+                [alias]
+                dev = "start --config ./dev.yaml --watch"
+                ui = ["console", "--port", "3000"]
+                list = "update"
+                "#,
+                user(),
+            )
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::default();
+        assert_eq!(config.network_timeout(), Duration::from_secs(30));
+        assert_eq!(config.update_check_timeout(), Duration::from_millis(500));
+        assert_eq!(config.update_check_interval_hours(), 24);
+        assert_eq!(config.github_token(), None);
+        assert_eq!(config.tamper_check(), TamperCheck::Warn);
+        assert_eq!(config.get("registry.url").unwrap().origin, Origin::Default);
+    }
+
+    #[test]
+    fn test_download_timeouts() {
+        let mut config = Config::default();
+        assert_eq!(config.download_connect_timeout(), Duration::from_secs(10));
+        assert_eq!(config.download_read_timeout(), Duration::from_secs(30));
+        assert_eq!(config.download_timeout(), None);
+
+        config
+            .merge_str("[download]\ntimeout_secs = 900\n", user())
+            .unwrap();
+        assert_eq!(config.download_timeout(), Some(Duration::from_secs(900)));
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let mut config = Config::default();
+        let system = Origin::System(PathBuf::from("/etc/iii-cli/config.toml"));
+        let project = Origin::Project(PathBuf::from("/work/.iii-cli.toml"));
+        config
+            .merge_str("[network]\ntimeout_secs = 10\n[update]\ncheck_interval_hours = 1\n", system.clone())
+            .unwrap();
+        config
+            .merge_str("[network]\ntimeout_secs = 20\n", project.clone())
+            .unwrap();
+        config
+            .merge_env(|name| (name == "III_NETWORK_TIMEOUT_SECS").then(|| "40".to_string()))
+            .unwrap();
+
+        assert_eq!(config.network_timeout(), Duration::from_secs(40));
+        assert_eq!(
+            config.get("network.timeout_secs").unwrap().origin,
+            Origin::Env("III_NETWORK_TIMEOUT_SECS")
+        );
+        assert_eq!(config.update_check_interval_hours(), 1);
+        assert_eq!(config.get("update.check_interval_hours").unwrap().origin, system);
+    }
+
+    #[test]
+    fn test_env_priority() {
+        let mut config = Config::default();
+        config
+            .merge_env(|name| match name {
+                "III_GITHUB_TOKEN" => Some("iii".to_string()),
+                "GITHUB_TOKEN" => Some("gh".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(config.github_token().as_deref(), Some("iii"));
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        let mut config = Config::default();
+        let result = config.merge_str("[network]\ntimeout_secs = \"slow\"\n", user());
+        assert!(matches!(result, Err(ConfigError::InvalidValue { .. })));
+
+        let result = config.merge_env(|name| {
+            (name == "III_UPDATE_CHECK_TIMEOUT_MS").then(|| "-5".to_string())
+        });
+        assert!(matches!(result, Err(ConfigError::InvalidValue { .. })));
+
+        let result = config.merge_str("[alias]\nnothing = \"  \"\n", user());
+        assert!(matches!(result, Err(ConfigError::InvalidValue { .. })));
+
+        let result = config.merge_str("[exec]\ntamper_check = \"loud\"\n", user());
+        assert!(matches!(result, Err(ConfigError::InvalidValue { .. })));
+        config
+            .merge_str("[exec]\ntamper_check = \"strict\"\n", user())
+            .unwrap();
+        assert_eq!(config.tamper_check(), TamperCheck::Strict);
+    }
+
+    #[test]
+    fn test_invalid_layer_is_skipped_whole() {
+        let mut config = Config::default();
+        config
+            .merge_str("[network]\ntimeout_secs = 10\n", user())
+            .unwrap();
+        let project = Origin::Project(PathBuf::from("/work/.iii-cli.toml"));
+        let result = config.merge_str(
+            "[network]\ntimeout_secs = 60\n[update]\ncheck_timeout_ms = \"soon\"\n",
+            project,
+        );
+        assert!(result.is_err());
+        assert_eq!(config.network_timeout(), Duration::from_secs(10));
+        assert_eq!(config.get("network.timeout_secs").unwrap().origin, user());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_ignored() {
+        let mut config = Config::default();
+        config
+            .merge_str("[future]\nsetting = true\n[network]\nproxy = \"x\"\n", user())
+            .unwrap();
+        assert!(config.get("future.setting").is_none());
+        assert!(config.get("network.proxy").is_none());
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        let config = config();
+        assert_eq!(
+            config.alias("dev").unwrap(),
+            vec!["start", "--config", "./dev.yaml", "--watch"]
+        );
+        assert_eq!(config.alias("ui").unwrap(), vec!["console", "--port", "3000"]);
+        assert_eq!(config.alias("missing"), None);
+    }
+
+    #[test]
+    fn test_expand_alias_keeps_surrounding_args() {
+        let expanded = config().expand_aliases(
+            os_args(&["iii-cli", "--no-update-check", "dev", "--verbose"]),
+            |_| false,
+        );
+        assert_eq!(
+            expanded,
+            os_args(&[
+                "iii-cli",
+                "--no-update-check",
+                "start",
+                "--config",
+                "./dev.yaml",
+                "--watch",
+                "--verbose",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_expand_only_command_position() {
+        let args = os_args(&["iii-cli", "console", "dev"]);
+        assert_eq!(config().expand_aliases(args.clone(), |_| false), args);
+    }
+
+    #[test]
+    fn test_reserved_command_is_not_shadowed() {
+        let args = os_args(&["iii-cli", "list"]);
+        assert_eq!(config().expand_aliases(args.clone(), |name| name == "list"), args);
+    }
+
+    #[test]
+    fn test_set_preserves_existing_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "# my settings\n[alias]\ndev = \"start\"\n").unwrap();
+
+        set(&path, "network.timeout_secs", "60").unwrap();
+        set(&path, "alias.ui", "console --port 3000").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# my settings\n"));
+        let mut config = Config::default();
+        config.merge_str(&content, user()).unwrap();
+        assert_eq!(config.network_timeout(), Duration::from_secs(60));
+        assert_eq!(config.alias("dev").unwrap(), vec!["start"]);
+        assert_eq!(config.alias("ui").unwrap(), vec!["console", "--port", "3000"]);
+    }
+
+    #[test]
+    fn test_set_rejects_unknown_key_and_bad_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        assert!(matches!(
+            set(&path, "network.proxy", "x"),
+            Err(ConfigError::UnknownKey { .. })
+        ));
+        assert!(matches!(
+            set(&path, "update.check_timeout_ms", "soon"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_project_cannot_set_trusted_settings() {
+        let project = Origin::Project(PathBuf::from("/work/.iii-cli.toml"));
+        for content in [
+            "[github]\ntoken = \"stolen\"\n",
+            "[registry]\nurl = \"https://evil.example/registry.json\"\n",
+            "[advisories]\nurl = \"https://evil.example/advisories.json\"\n",
+            "[exec]\ntamper_check = \"off\"\n",
+        ] {
+            let result = Config::default().merge_str(content, project.clone());
+            assert!(
+                matches!(result, Err(ConfigError::NotAllowedInProject { .. })),
+                "{}",
+                content
+            );
+        }
+        assert!(Config::default()
+            .merge_str("[network]\ntimeout_secs = 60\n", project)
+            .is_ok());
+        assert!(Config::default()
+            .merge_str("[exec]\ntamper_check = \"off\"\n", user())
+            .is_ok());
+
+        let path = Path::new(".iii-cli.toml");
+        assert!(matches!(
+            check_scope(Scope::Project, "github.token", path),
+            Err(ConfigError::NotAllowedInProject { .. })
+        ));
+        assert!(check_scope(Scope::User, "github.token", path).is_ok());
+        assert!(check_scope(Scope::Project, "alias.dev", path).is_ok());
+    }
+}
@@ -0,0 +1,419 @@
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::config;
+use crate::github;
+use crate::platform;
+use crate::registry;
+use crate::state::AppState;
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// One diagnostic result, with a hint on how to fix anything not ok.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    /// Short identifier of the check (e.g., "path", "state")
+    pub check: &'static str,
+    pub status: Status,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Finding {
+    fn ok(check: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Ok,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warning(check: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Warning,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn error(check: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Error,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run every check. Network checks use `client` when one could be built.
+pub async fn run(client: Option<&reqwest::Client>, state: &AppState) -> Vec<Finding> {
+    let mut findings = vec![check_path()];
+    findings.push(check_writable("bin-dir", &platform::bin_dir()));
+    findings.push(check_writable("data-dir", &platform::data_dir()));
+    findings.push(check_leftover_temp_files());
+    findings.extend(check_state(state));
+    findings.extend(check_shadowing(state));
+    findings.extend(check_github(client).await);
+    findings.extend(check_platform_support());
+    findings
+}
+
+/// Print findings for humans.
+pub fn print_findings(findings: &[Finding]) {
+    for finding in findings {
+        let marker = match finding.status {
+            Status::Ok => "✓".green(),
+            Status::Warning => "!".yellow(),
+            Status::Error => "✗".red(),
+        };
+        eprintln!("  {} {}", marker, finding.message);
+        if let Some(hint) = &finding.hint {
+            eprintln!("      {} {}", "hint:".dimmed(), hint);
+        }
+    }
+
+    let errors = findings.iter().filter(|f| f.status == Status::Error).count();
+    let warnings = findings.iter().filter(|f| f.status == Status::Warning).count();
+    eprintln!();
+    if errors == 0 && warnings == 0 {
+        eprintln!("  {} No problems found", "✓".green());
+    } else {
+        eprintln!("  {} error(s), {} warning(s)", errors, warnings);
+    }
+}
+
+fn check_path() -> Finding {
+    let bin = platform::bin_dir();
+    if platform::bin_dir_on_path() {
+        return Finding::ok("path", format!("{} is on PATH", bin.display()));
+    }
+
+    let hint = if cfg!(target_os = "windows") {
+        format!(
+            "add {} to your user PATH in System Properties > Environment Variables",
+            bin.display()
+        )
+    } else {
+        format!(
+            "add `export PATH=\"{}:$PATH\"` to your shell profile",
+            bin.display()
+        )
+    };
+    Finding::warning(
+        "path",
+        format!(
+            "{} is not on PATH; installed binaries only run through iii-cli",
+            bin.display()
+        ),
+        hint,
+    )
+}
+
+/// Check that a directory exists and a file can be created in it.
+fn check_writable(check: &'static str, dir: &Path) -> Finding {
+    if !dir.exists() {
+        return Finding::warning(
+            check,
+            format!("{} does not exist yet", dir.display()),
+            "it is created on first install; run `iii-cli install <command>`",
+        );
+    }
+
+    let probe = dir.join(format!(".iii-cli-doctor-{}", std::process::id()));
+    let result = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe);
+    match result {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            Finding::ok(check, format!("{} is writable", dir.display()))
+        }
+        Err(e) => Finding::error(
+            check,
+            format!("{} is not writable: {}", dir.display(), e),
+            format!("fix the ownership or permissions of {}", dir.display()),
+        ),
+    }
+}
+
+/// Temp files left behind by an interrupted atomic write.
+fn check_leftover_temp_files() -> Finding {
+    let mut names: Vec<&str> = registry::all_binaries().iter().map(|s| s.name).collect();
+    names.push(registry::SELF_SPEC.name);
+
+    let mut leftovers = leftover_temp_files(&platform::bin_dir(), &names);
+    leftovers.extend(leftover_temp_files_recursive(&platform::versions_dir()));
+    leftovers.extend(leftover_temp_files_recursive(&platform::downloads_dir()));
+    let state_tmp = platform::state_file_path().with_extension("json.tmp");
+    if state_tmp.is_file() {
+        leftovers.push(state_tmp);
+    }
+
+    if leftovers.is_empty() {
+        return Finding::ok("temp-files", "No leftover temporary files");
+    }
+
+    let list = leftovers
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Finding::warning(
+        "temp-files",
+        format!(
+            "{} leftover temporary file(s) from an interrupted install",
+            leftovers.len()
+        ),
+        format!("remove them: rm {}", list),
+    )
+}
+
+/// `<name>.tmp` files in `dir` for the given binary names only, since
+/// bin_dir() may be shared with other tools.
+fn leftover_temp_files(dir: &Path, names: &[&str]) -> Vec<PathBuf> {
+    names
+        .iter()
+        .map(|name| dir.join(format!("{}.tmp", name)))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Every `*.tmp` file under `dir`.
+fn leftover_temp_files_recursive(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return found;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            found.extend(leftover_temp_files_recursive(&path));
+        } else if path.extension().is_some_and(|ext| ext == "tmp") {
+            found.push(path);
+        }
+    }
+    found
+}
+
+/// State entries whose binary is gone from bin_dir().
+fn check_state(state: &AppState) -> Vec<Finding> {
+    let mut names: Vec<&String> = state.binaries.keys().collect();
+    names.sort();
+
+    let missing: Vec<Finding> = names
+        .into_iter()
+        .filter(|name| !platform::binary_path(name).exists())
+        .map(|name| {
+            let command = registry::primary_command(name).unwrap_or(name.as_str());
+            Finding::error(
+                "state",
+                format!(
+                    "{} v{} is recorded as installed but {} is missing",
+                    name,
+                    state.binaries[name].version,
+                    platform::binary_path(name).display()
+                ),
+                format!(
+                    "reinstall with `iii-cli install {}` or forget it with `iii-cli uninstall {}`",
+                    command, command
+                ),
+            )
+        })
+        .collect();
+
+    if missing.is_empty() {
+        vec![Finding::ok("state", "Every installed binary is present on disk")]
+    } else {
+        missing
+    }
+}
+
+/// Managed binaries that a different file earlier on PATH hides.
+fn check_shadowing(state: &AppState) -> Vec<Finding> {
+    let mut names: Vec<&String> = state.binaries.keys().collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let managed = platform::binary_path(name);
+            let found = platform::which_binary(&platform::exe_name(name))?;
+            if same_file(&found, &managed) {
+                return None;
+            }
+            Some(Finding::warning(
+                "shadowed",
+                format!(
+                    "`{}` on PATH runs {}, not the managed {}",
+                    name,
+                    found.display(),
+                    managed.display()
+                ),
+                format!(
+                    "remove {} or put {} earlier on PATH",
+                    found.display(),
+                    platform::bin_dir().display()
+                ),
+            ))
+        })
+        .collect()
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// GitHub token presence and remaining API requests.
+async fn check_github(client: Option<&reqwest::Client>) -> Vec<Finding> {
+    let settings = config::current();
+    let mut findings = Vec::new();
+
+    match settings.get("github.token").filter(|_| settings.github_token().is_some()) {
+        Some(entry) => findings.push(Finding::ok(
+            "github-token",
+            format!("GitHub token set ({})", entry.origin),
+        )),
+        None => findings.push(Finding::warning(
+            "github-token",
+            "No GitHub token set; anonymous requests are limited to 60 per hour",
+            "set GITHUB_TOKEN or run `iii-cli config set github.token <token>`",
+        )),
+    }
+
+    let Some(client) = client else {
+        return findings;
+    };
+    match github::fetch_rate_limit(client).await {
+        Ok(rate) => findings.push(rate_limit_finding(&rate)),
+        Err(e) => findings.push(Finding::warning(
+            "rate-limit",
+            format!("Could not query the GitHub rate limit: {}", e),
+            "check your network connection and proxy settings",
+        )),
+    }
+    findings
+}
+
+fn rate_limit_finding(rate: &github::RateLimit) -> Finding {
+    let message = format!(
+        "GitHub API: {} of {} requests remaining",
+        rate.remaining, rate.limit
+    );
+    let reset = chrono::DateTime::from_timestamp(rate.reset, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+        .unwrap_or_else(|| "later".to_string());
+
+    if rate.remaining == 0 {
+        Finding::error(
+            "rate-limit",
+            message,
+            format!("wait until {} or set a GitHub token", reset),
+        )
+    } else if rate.remaining * 10 < rate.limit {
+        Finding::warning(
+            "rate-limit",
+            message,
+            format!("the limit resets at {}; set a GitHub token for more headroom", reset),
+        )
+    } else {
+        Finding::ok("rate-limit", message)
+    }
+}
+
+/// Whether each registry binary is available for this platform.
+fn check_platform_support() -> Vec<Finding> {
+    std::iter::once(&registry::SELF_SPEC)
+        .chain(registry::all_binaries())
+        .map(|spec| match platform::check_platform_support(spec) {
+            Ok(()) => Finding::ok(
+                "platform",
+                format!("{} is available for {}", spec.name, platform::current_target()),
+            ),
+            Err(e) => Finding::warning(
+                "platform",
+                e.to_string(),
+                format!("commands backed by {} will not work on this machine", spec.name),
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leftover_temp_files_only_matches_known_names() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("iii.tmp"), "").unwrap();
+        std::fs::write(dir.path().join("other-tool.tmp"), "").unwrap();
+        std::fs::write(dir.path().join("iii-console"), "").unwrap();
+
+        let found = leftover_temp_files(dir.path(), &["iii", "iii-console"]);
+        assert_eq!(found, vec![dir.path().join("iii.tmp")]);
+    }
+
+    #[test]
+    fn test_leftover_temp_files_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("iii").join("0.4.2");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("iii.tmp"), "").unwrap();
+        std::fs::write(nested.join("iii"), "").unwrap();
+
+        assert_eq!(
+            leftover_temp_files_recursive(dir.path()),
+            vec![nested.join("iii.tmp")]
+        );
+    }
+
+    #[test]
+    fn test_check_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_writable("dir", dir.path()).status, Status::Ok);
+        assert_eq!(
+            check_writable("dir", &dir.path().join("missing")).status,
+            Status::Warning
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_rate_limit_finding() {
+        let rate = |remaining| github::RateLimit {
+            limit: 60,
+            remaining,
+            reset: 0,
+        };
+        assert_eq!(rate_limit_finding(&rate(50)).status, Status::Ok);
+        assert_eq!(rate_limit_finding(&rate(5)).status, Status::Warning);
+        assert_eq!(rate_limit_finding(&rate(0)).status, Status::Error);
+    }
+
+    #[test]
+    fn test_findings_serialize_for_json() {
+        let finding = Finding::warning("path", "not on PATH", "add it");
+        let json = serde_json::to_value(&finding).unwrap();
+        assert_eq!(json["status"], "warning");
+        assert_eq!(json["hint"], "add it");
+        let json = serde_json::to_value(Finding::ok("path", "fine")).unwrap();
+        assert!(json.get("hint").is_none());
+    }
+}
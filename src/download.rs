@@ -1,57 +1,281 @@
-use std::path::Path;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use futures_util::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::error::{DownloadError, ExtractError};
-use crate::github::ReleaseAsset;
+use crate::cache::Cache;
+use crate::error::{DownloadError, DownloadTimeout, ExtractError, NetworkError};
+use crate::github::{self, ReleaseAsset};
+use crate::{config, platform, policy};
 use crate::registry::BinarySpec;
+use crate::retry::{self, Retrier, RetryPolicy};
+use crate::store::ArtifactHashes;
+
+/// Where the expected SHA256 of a downloaded archive comes from.
+#[derive(Debug, Clone, Copy)]
+pub enum Checksum<'a> {
+    /// URL of the `.sha256` sidecar asset published with the release
+    Sidecar(&'a str),
+    /// Hash recorded in a lockfile; the archive must match it exactly
+    Locked(&'a str),
+    /// Hash recorded when the version was first installed; used to repair it
+    Recorded(&'a str),
+    /// No checksum available; verification is skipped with a warning
+    Unavailable,
+}
+
+impl Checksum<'_> {
+    /// How an archive downloaded with this checksum ends up verified.
+    pub fn status(&self) -> ChecksumStatus {
+        match self {
+            Self::Sidecar(_) => ChecksumStatus::Verified,
+            Self::Locked(_) => ChecksumStatus::Locked,
+            Self::Recorded(_) => ChecksumStatus::Recorded,
+            Self::Unavailable => ChecksumStatus::Unverified,
+        }
+    }
+}
+
+/// How a downloaded archive was verified, as recorded in the install history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumStatus {
+    /// Matched the `.sha256` sidecar published with the release
+    Verified,
+    /// Matched the lockfile
+    Locked,
+    /// Matched the hash recorded when the version was first installed
+    Recorded,
+    /// Not verified: no checksum was available
+    Unverified,
+}
+
+impl std::fmt::Display for ChecksumStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Verified => "verified",
+            Self::Locked => "locked",
+            Self::Recorded => "recorded",
+            Self::Unverified => "unverified",
+        };
+        f.pad(s)
+    }
+}
 
 /// Download an asset with a progress bar, verify checksum if available,
-/// and extract the binary to the target path using atomic write.
+/// and extract the binary to the target path using atomic write. Archives
+/// are served from and kept in the download cache.
+///
+/// Returns the SHA256 of the archive and of the extracted binary.
 pub async fn download_and_install(
     client: &reqwest::Client,
     spec: &BinarySpec,
     asset: &ReleaseAsset,
-    checksum_url: Option<&str>,
+    tag: &str,
+    checksum: Checksum<'_>,
     target_path: &Path,
-) -> Result<(), DownloadAndInstallError> {
-    // Download the asset with progress
-    let archive_bytes = download_with_progress(client, &asset.browser_download_url, asset.size).await?;
+) -> Result<ArtifactHashes, DownloadAndInstallError> {
+    // A cached archive is only used if it could be downloaded now
+    policy::current()
+        .check_url(&asset.browser_download_url)
+        .map_err(DownloadError::from)?;
 
-    // Verify checksum if available
-    if let Some(checksum_url) = checksum_url {
-        verify_checksum(client, checksum_url, &archive_bytes, &asset.name).await?;
-    } else {
+    // Refuse unverifiable archives before downloading when policy requires checksums
+    if matches!(checksum, Checksum::Unavailable) {
+        policy::current()
+            .check_unverified(spec.name)
+            .map_err(DownloadError::from)?;
+    }
+
+    let cache_limit = config::current().cache_max_size();
+    let mut cache = (cache_limit > 0).then(Cache::open_default);
+
+    // Use a cached archive if it still passes verification
+    if let Some(cache) = cache.as_mut() {
+        let known = match checksum {
+            Checksum::Locked(expected) | Checksum::Recorded(expected) => Some(expected),
+            Checksum::Sidecar(_) | Checksum::Unavailable => None,
+        };
+        if let Some((blob, archive_sha256)) = cache.find(spec.repo, tag, &asset.name, known) {
+            match verify_archive(client, spec, asset, checksum, &archive_sha256).await {
+                Ok(()) => {
+                    eprintln!("  Using cached {}", asset.name);
+                    let binary_sha256 = extract_binary(spec.name, &blob, target_path)?;
+                    save_cache(cache, cache_limit);
+                    return Ok(ArtifactHashes {
+                        archive_sha256,
+                        binary_sha256,
+                    });
+                }
+                // The release asset changed since it was cached
+                Err(e) if e.is_checksum_mismatch() => cache.forget(spec.repo, tag, &asset.name),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // Stream the asset to a partial file, resuming an earlier attempt if possible
+    let (archive_path, archive_sha256) = download_resumable(asset).await?;
+    // Whatever happens next, the partial download is finished with
+    let archive = TempFile::new(archive_path).map_err(ExtractError::from)?;
+    let _meta = TempFile::new(partial_meta_path(archive.path())).map_err(ExtractError::from)?;
+
+    verify_archive(client, spec, asset, checksum, &archive_sha256).await?;
+
+    // Keep the verified archive for reinstalls
+    let cached = cache.as_mut().and_then(|cache| {
+        cache
+            .insert(spec.repo, tag, &asset.name, &archive_sha256, archive.path())
+            .inspect_err(|e| {
+                eprintln!(
+                    "  {} Failed to cache {}: {}",
+                    colored::Colorize::yellow("warning:"),
+                    asset.name,
+                    e
+                )
+            })
+            .ok()
+    });
+    let archive_path = cached.as_deref().unwrap_or(archive.path());
+
+    // Extract the binary straight to a temp file next to the target, then rename
+    let binary_sha256 = extract_binary(spec.name, archive_path, target_path)?;
+
+    if let Some(cache) = cache.as_mut() {
+        save_cache(cache, cache_limit);
+    }
+
+    Ok(ArtifactHashes {
+        archive_sha256,
+        binary_sha256,
+    })
+}
+
+/// Check an archive's SHA256 against the expected checksum.
+async fn verify_archive(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    asset: &ReleaseAsset,
+    checksum: Checksum<'_>,
+    archive_sha256: &str,
+) -> Result<(), DownloadError> {
+    match checksum {
+        Checksum::Sidecar(checksum_url) => {
+            verify_checksum(client, checksum_url, archive_sha256, &asset.name).await
+        }
+        Checksum::Locked(expected) => verify_locked_checksum(expected, archive_sha256, &asset.name),
+        Checksum::Recorded(expected) => match mismatch(expected, archive_sha256) {
+            Some((expected, actual)) => Err(DownloadError::RecordedChecksumMismatch {
+                asset: asset.name.clone(),
+                expected,
+                actual,
+            }),
+            None => Ok(()),
+        },
+        Checksum::Unavailable => {
+            eprintln!(
+                "  {} Checksum not available for {}, skipping verification",
+                colored::Colorize::yellow("warning:"),
+                spec.name
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Bring the cache back within `limit` bytes, which may have been lowered
+/// since the last download, and save it. The cache only saves downloads, so
+/// failing to update it is not an error.
+fn save_cache(cache: &mut Cache, limit: u64) {
+    cache.evict(limit);
+    if let Err(e) = cache.save() {
         eprintln!(
-            "  {} Checksum not available for {}, skipping verification",
+            "  {} Failed to update the download cache: {}",
             colored::Colorize::yellow("warning:"),
-            spec.name
+            e
         );
     }
+}
 
-    // Extract binary from archive
-    let binary_bytes = extract_binary(spec.name, &archive_bytes)?;
-
-    // Atomic write: write to temp file, then rename
-    atomic_write_binary(&binary_bytes, target_path)?;
+/// Download an asset and return the SHA256 of its contents, without
+/// keeping the contents.
+pub async fn download_sha256(asset: &ReleaseAsset) -> Result<String, DownloadError> {
+    let url = &asset.browser_download_url;
+    policy::current().check_url(url)?;
+    let client = github::build_download_client()?;
 
-    Ok(())
+    let mut retrier = Retrier::new(RetryPolicy::from_config());
+    let download = async {
+        loop {
+            let response = retrier
+                .send(github::get(&client, url))
+                .await
+                .map_err(|e| request_failed(asset, e))?
+                .error_for_status()?;
+            match stream_with_progress(response, 0, asset.size, Sha256::new(), &mut std::io::sink())
+                .await
+            {
+                // Nothing is kept, so a dropped connection starts over
+                Err(DownloadError::Http(e)) if retry::is_transient(&e) => {
+                    if !retrier.backoff(&e, None).await {
+                        return Err(request_failed(asset, retrier.give_up(e)));
+                    }
+                }
+                result => return result,
+            }
+        }
+    };
+    match within_total_timeout(download).await {
+        Ok(result) => result,
+        Err(limit) => Err(total_timeout(asset, &retrier, limit)),
+    }
 }
 
-/// Download a file with a progress bar showing download progress.
-async fn download_with_progress(
+/// Fetch a `.sha256` sidecar file and return the hash it declares.
+pub async fn fetch_sidecar_checksum(
     client: &reqwest::Client,
-    url: &str,
-    total_size: u64,
-) -> Result<Vec<u8>, DownloadError> {
-    let response = client.get(url).send().await?;
+    checksum_url: &str,
+) -> Result<String, DownloadError> {
+    policy::current().check_url(checksum_url)?;
+    let checksum_response = retry::send(github::get(client, checksum_url)).await?;
+    let checksum_text = checksum_response
+        .text()
+        .await
+        .map_err(|e| DownloadError::Failed(format!("Failed to read checksum: {}", e)))?;
+
+    // Checksum file format: "hash  filename" or just "hash"
+    checksum_text
+        .split_whitespace()
+        .next()
+        .map(|hash| hash.to_lowercase())
+        .ok_or_else(|| DownloadError::Failed("Empty checksum file".to_string()))
+}
 
+/// Compute the lowercase hex SHA256 of a byte slice.
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Write a response body to `out`, continuing `hasher` and a progress bar
+/// from `offset` bytes already on disk. Returns the SHA256 of the contents.
+async fn stream_with_progress(
+    response: reqwest::Response,
+    offset: u64,
+    total_size: u64,
+    mut hasher: Sha256,
+    out: &mut impl Write,
+) -> Result<String, DownloadError> {
     let total = if total_size > 0 {
         total_size
     } else {
-        response.content_length().unwrap_or(0)
+        response.content_length().map_or(0, |len| len + offset)
     };
 
     let pb = ProgressBar::new(total);
@@ -62,43 +286,299 @@ async fn download_with_progress(
         .unwrap()
         .progress_chars("=> "),
     );
+    pb.set_position(offset);
 
-    let mut bytes = Vec::with_capacity(total as usize);
+    let mut downloaded = offset;
     let mut stream = response.bytes_stream();
 
     while let Some(chunk) = stream.next().await {
-        let chunk = chunk?;
-        bytes.extend_from_slice(&chunk);
-        pb.set_position(bytes.len() as u64);
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                pb.abandon();
+                return Err(e.into());
+            }
+        };
+        hasher.update(&chunk);
+        out.write_all(&chunk)
+            .map_err(|e| DownloadError::Failed(format!("Failed to write download: {}", e)))?;
+        downloaded += chunk.len() as u64;
+        pb.set_position(downloaded);
     }
 
     pb.finish_and_clear();
-    Ok(bytes)
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
-/// Verify SHA256 checksum against a sidecar file.
+/// Metadata kept next to a partial download, used to resume it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PartialDownload {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+    expected_size: u64,
+}
+
+impl PartialDownload {
+    /// Metadata for a response, or None when it has no validator to resume
+    /// against safely.
+    fn from_response(url: &str, expected_size: u64, response: &reqwest::Response) -> Option<Self> {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let partial = Self {
+            url: url.to_string(),
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+            expected_size,
+        };
+        partial.validator().is_some().then_some(partial)
+    }
+
+    /// Value for `If-Range`. A strong ETag is preferred; weak ETags are not
+    /// allowed in `If-Range`.
+    fn validator(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Path of the partial download of an asset.
+fn partial_path(asset_name: &str) -> PathBuf {
+    platform::downloads_dir().join(format!("{}.part", asset_name))
+}
+
+/// Path of the metadata stored next to a partial download.
+fn partial_meta_path(partial: &Path) -> PathBuf {
+    let mut path = partial.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// Bytes of an earlier partial download that can be resumed, with the
+/// metadata it was started from.
+fn resumable_offset(asset: &ReleaseAsset, partial: &Path) -> Option<(u64, PartialDownload)> {
+    let meta = PartialDownload::load(&partial_meta_path(partial))?;
+    if meta.url != asset.browser_download_url || meta.expected_size != asset.size {
+        return None;
+    }
+    let len = std::fs::metadata(partial).ok()?.len();
+    let complete = asset.size > 0 && len >= asset.size;
+    (len > 0 && !complete).then_some((len, meta))
+}
+
+/// `Content-Range: bytes <start>-<end>/<size>` starts at `offset`.
+fn content_range_starts_at(response: &reqwest::Response, offset: u64) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("bytes "))
+        .and_then(|v| v.split_once('-'))
+        .and_then(|(start, _)| start.parse::<u64>().ok())
+        == Some(offset)
+}
+
+/// Download an asset to its partial file in downloads_dir(). A partial file
+/// left by an interrupted download is resumed with a `Range` request when
+/// the server still serves the same content; otherwise the download starts
+/// over. The partial file is kept if the download fails again.
+///
+/// Returns the path of the complete file and its SHA256.
+async fn download_resumable(
+    asset: &ReleaseAsset,
+) -> Result<(PathBuf, String), DownloadAndInstallError> {
+    std::fs::create_dir_all(platform::downloads_dir()).map_err(ExtractError::from)?;
+    let client = github::build_download_client().map_err(DownloadError::from)?;
+    let partial = partial_path(&asset.name);
+
+    // A connection dropped mid-download is retried like a failed request,
+    // resuming from what already arrived
+    let mut retrier = Retrier::new(RetryPolicy::from_config());
+    let download = async {
+        loop {
+            match download_attempt(&client, asset, &partial, &mut retrier).await {
+                Err(DownloadAndInstallError::Download(DownloadError::Http(e)))
+                    if retry::is_transient(&e) =>
+                {
+                    if !retrier.backoff(&e, None).await {
+                        return Err(request_failed(asset, retrier.give_up(e)).into());
+                    }
+                }
+                result => return result,
+            }
+        }
+    };
+    match within_total_timeout(download).await {
+        Ok(result) => result,
+        Err(limit) => Err(total_timeout(asset, &retrier, limit).into()),
+    }
+}
+
+/// Run a download, giving up once `download.timeout_secs` has passed.
+/// Returns the limit if it was reached.
+async fn within_total_timeout<T>(download: impl Future<Output = T>) -> Result<T, Duration> {
+    match config::current().download_timeout() {
+        Some(limit) => tokio::time::timeout(limit, download)
+            .await
+            .map_err(|_| limit),
+        None => Ok(download.await),
+    }
+}
+
+fn total_timeout(asset: &ReleaseAsset, retrier: &Retrier, limit: Duration) -> DownloadError {
+    DownloadError::Timeout {
+        asset: asset.name.clone(),
+        attempts: retrier.attempts(),
+        timeout: DownloadTimeout::Total(limit),
+    }
+}
+
+/// The error for a download request that failed for good, naming the
+/// timeout that fired if it was one.
+fn request_failed(asset: &ReleaseAsset, error: NetworkError) -> DownloadError {
+    let (attempts, source) = match &error {
+        NetworkError::RequestFailed(source) => (1, source),
+        NetworkError::RetriesExhausted { attempts, source } => (*attempts, source),
+        _ => return error.into(),
+    };
+    match timeout_of(source) {
+        Some(timeout) => DownloadError::Timeout {
+            asset: asset.name.clone(),
+            attempts,
+            timeout,
+        },
+        None => error.into(),
+    }
+}
+
+/// Which of the download client's timeouts a request error comes from.
+fn timeout_of(error: &reqwest::Error) -> Option<DownloadTimeout> {
+    if !error.is_timeout() {
+        return None;
+    }
+    let config = config::current();
+    Some(if error.is_connect() {
+        DownloadTimeout::Connect(config.download_connect_timeout())
+    } else {
+        DownloadTimeout::ReadIdle(config.download_read_timeout())
+    })
+}
+
+/// One attempt of `download_resumable`, writing to `partial`.
+async fn download_attempt(
+    client: &reqwest::Client,
+    asset: &ReleaseAsset,
+    partial: &Path,
+    retrier: &mut Retrier,
+) -> Result<(PathBuf, String), DownloadAndInstallError> {
+    let url = &asset.browser_download_url;
+    let meta_path = partial_meta_path(partial);
+
+    let resume = resumable_offset(asset, partial);
+    let mut request = github::get(client, url);
+    if let Some((offset, meta)) = &resume {
+        if let Some(validator) = meta.validator() {
+            request = request
+                .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+                .header(reqwest::header::IF_RANGE, validator);
+        }
+    }
+    let mut response = retrier
+        .send(request)
+        .await
+        .map_err(|e| request_failed(asset, e))?;
+
+    let resumed_at = resume.map(|(offset, _)| offset).filter(|&offset| {
+        response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+            && content_range_starts_at(&response, offset)
+    });
+    // The partial file no longer fits the asset, or the server sent a range
+    // other than the one asked for; fetch it whole
+    let wrong_range =
+        response.status() == reqwest::StatusCode::PARTIAL_CONTENT && resumed_at.is_none();
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE || wrong_range {
+        response = retrier
+            .send(github::get(client, url))
+            .await
+            .map_err(|e| request_failed(asset, e))?;
+        // Part of a file cannot be written as the whole of it
+        if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(DownloadError::Failed(format!(
+                "{} answered a plain request for {} with partial content",
+                url, asset.name
+            ))
+            .into());
+        }
+    }
+    let response = response.error_for_status().map_err(DownloadError::from)?;
+
+    let (mut file, offset, hasher) = match resumed_at {
+        Some(offset) => {
+            eprintln!(
+                "  Resuming download of {} at {} of {} bytes",
+                asset.name, offset, asset.size
+            );
+            // Seed the hash with the bytes already on disk
+            let mut hasher = Sha256::new();
+            std::io::copy(
+                &mut std::fs::File::open(partial).map_err(ExtractError::from)?,
+                &mut hasher,
+            )
+            .map_err(ExtractError::from)?;
+            let file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(partial)
+                .map_err(ExtractError::from)?;
+            (file, offset, hasher)
+        }
+        None => {
+            // Metadata describes the response being written, so a download
+            // without a validator is never resumed
+            match PartialDownload::from_response(url, asset.size, &response) {
+                Some(meta) => meta.save(&meta_path).map_err(ExtractError::from)?,
+                None => {
+                    let _ = std::fs::remove_file(&meta_path);
+                }
+            }
+            let file = std::fs::File::create(partial).map_err(ExtractError::from)?;
+            (file, 0, Sha256::new())
+        }
+    };
+
+    let result = stream_with_progress(response, offset, asset.size, hasher, &mut file).await;
+    // Keep what arrived for the next attempt, even if the stream failed
+    file.sync_all().map_err(ExtractError::from)?;
+    let hash = result?;
+    Ok((partial.to_path_buf(), hash))
+}
+
+/// Verify an archive's SHA256 against a sidecar file.
 async fn verify_checksum(
     client: &reqwest::Client,
     checksum_url: &str,
-    data: &[u8],
+    actual: &str,
     asset_name: &str,
 ) -> Result<(), DownloadError> {
-    let checksum_response = client.get(checksum_url).send().await?;
-    let checksum_text = checksum_response
-        .text()
-        .await
-        .map_err(|e| DownloadError::Failed(format!("Failed to read checksum: {}", e)))?;
-
-    // Checksum file format: "hash  filename" or just "hash"
-    let expected = checksum_text
-        .split_whitespace()
-        .next()
-        .ok_or_else(|| DownloadError::Failed("Empty checksum file".to_string()))?
-        .to_lowercase();
-
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let actual = format!("{:x}", hasher.finalize());
+    let expected = fetch_sidecar_checksum(client, checksum_url).await?;
+    let actual = actual.to_string();
 
     if actual != expected {
         return Err(DownloadError::ChecksumMismatch {
@@ -111,26 +591,73 @@ async fn verify_checksum(
     Ok(())
 }
 
-/// Extract a binary from a tar.gz archive.
-fn extract_binary(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>, ExtractError> {
-    #[cfg(not(target_os = "windows"))]
-    {
-        extract_from_targz(binary_name, archive_bytes)
+/// Verify an archive's SHA256 against the hash recorded in a lockfile.
+fn verify_locked_checksum(expected: &str, actual: &str, asset_name: &str) -> Result<(), DownloadError> {
+    if let Some((expected, actual)) = mismatch(expected, actual) {
+        return Err(DownloadError::LockedChecksumMismatch {
+            asset: asset_name.to_string(),
+            expected,
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+/// Compare a computed SHA256 against a known one. Returns (expected, actual)
+/// on mismatch.
+fn mismatch(expected: &str, actual: &str) -> Option<(String, String)> {
+    let expected = expected.to_lowercase();
+    (actual != expected).then(|| (expected, actual.to_string()))
+}
+
+/// Extract a binary from a downloaded archive to `target_path`, writing it
+/// to a temp file in the same directory and renaming it into place.
+/// Returns the SHA256 of the binary.
+fn extract_binary(
+    binary_name: &str,
+    archive_path: &Path,
+    target_path: &Path,
+) -> Result<String, ExtractError> {
+    // Ensure parent directory exists
+    if let Some(parent) = target_path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
-    #[cfg(target_os = "windows")]
+
+    let temp = TempFile::new(target_path.with_extension("tmp"))?;
+    let binary_sha256 = {
+        let mut out = HashingWriter::new(std::fs::File::create(temp.path())?);
+        let archive = std::fs::File::open(archive_path)?;
+        #[cfg(not(target_os = "windows"))]
+        extract_from_targz(binary_name, archive, &mut out)?;
+        #[cfg(target_os = "windows")]
+        extract_from_zip(binary_name, archive, &mut out)?;
+        out.finish()?
+    };
+
+    // Set executable permission on Unix
+    #[cfg(unix)]
     {
-        extract_from_zip(binary_name, archive_bytes)
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(temp.path(), std::fs::Permissions::from_mode(0o755))?;
     }
+
+    // Atomic rename
+    temp.persist(target_path)?;
+    Ok(binary_sha256)
 }
 
-/// Extract a binary from a tar.gz archive.
+/// Copy a binary out of a tar.gz archive.
 #[cfg(not(target_os = "windows"))]
-fn extract_from_targz(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>, ExtractError> {
+fn extract_from_targz(
+    binary_name: &str,
+    archive: std::fs::File,
+    out: &mut impl Write,
+) -> Result<(), ExtractError> {
     use flate2::read::GzDecoder;
-    use std::io::Read;
     use tar::Archive;
 
-    let decoder = GzDecoder::new(archive_bytes);
+    let decoder = GzDecoder::new(std::io::BufReader::new(archive));
     let mut archive = Archive::new(decoder);
 
     let exe_name = binary_name;
@@ -148,11 +675,9 @@ fn extract_from_targz(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>
             .unwrap_or("");
 
         if file_name == exe_name {
-            let mut buf = Vec::new();
-            entry
-                .read_to_end(&mut buf)
+            std::io::copy(&mut entry, out)
                 .map_err(|e| ExtractError::ExtractionFailed(e.to_string()))?;
-            return Ok(buf);
+            return Ok(());
         }
     }
 
@@ -162,13 +687,14 @@ fn extract_from_targz(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>
     )))
 }
 
-/// Extract a binary from a zip archive (Windows).
+/// Copy a binary out of a zip archive (Windows).
 #[cfg(target_os = "windows")]
-fn extract_from_zip(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>, ExtractError> {
-    use std::io::{Cursor, Read};
-
-    let reader = Cursor::new(archive_bytes);
-    let mut archive = zip::ZipArchive::new(reader)
+fn extract_from_zip(
+    binary_name: &str,
+    archive: std::fs::File,
+    out: &mut impl Write,
+) -> Result<(), ExtractError> {
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(archive))
         .map_err(|e| ExtractError::ExtractionFailed(e.to_string()))?;
 
     let exe_name = format!("{}.exe", binary_name);
@@ -185,10 +711,9 @@ fn extract_from_zip(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>,
             .unwrap_or("");
 
         if file_name == exe_name || file_name == binary_name {
-            let mut buf = Vec::new();
-            file.read_to_end(&mut buf)
+            std::io::copy(&mut file, out)
                 .map_err(|e| ExtractError::ExtractionFailed(e.to_string()))?;
-            return Ok(buf);
+            return Ok(());
         }
     }
 
@@ -198,38 +723,67 @@ fn extract_from_zip(binary_name: &str, archive_bytes: &[u8]) -> Result<Vec<u8>,
     )))
 }
 
-/// Atomically write binary data to the target path.
-/// Writes to a temp file in the same directory, then renames.
-fn atomic_write_binary(data: &[u8], target_path: &Path) -> Result<(), ExtractError> {
-    use std::io::Write;
+/// A writer that computes the SHA256 of everything written through it.
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
 
-    // Ensure parent directory exists
-    if let Some(parent) = target_path.parent() {
-        std::fs::create_dir_all(parent)?;
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Flush the inner writer and return the hash.
+    fn finish(mut self) -> std::io::Result<String> {
+        self.inner.flush()?;
+        Ok(format!("{:x}", self.hasher.finalize()))
     }
+}
 
-    let temp_path = target_path.with_extension("tmp");
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
 
-    // Write to temp file
-    let mut file = std::fs::File::create(&temp_path)?;
-    file.write_all(data)?;
-    file.flush()?;
-    drop(file);
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
 
-    // Set executable permission on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o755))?;
+/// A file that is removed when dropped unless it was renamed into place.
+struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    fn new(path: PathBuf) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
     }
 
-    // Atomic rename
-    std::fs::rename(&temp_path, target_path).map_err(|e| {
-        let _ = std::fs::remove_file(&temp_path);
-        ExtractError::Io(e)
-    })?;
+    fn path(&self) -> &Path {
+        &self.path
+    }
 
-    Ok(())
+    /// Rename the file to `target`.
+    fn persist(self, target: &Path) -> std::io::Result<()> {
+        std::fs::rename(&self.path, target)
+        // On failure, dropping self removes the temp file
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
 }
 
 /// Error type combining download and extraction errors.
@@ -256,5 +810,262 @@ mod tests {
             hash,
             "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
         );
+        assert_eq!(sha256_hex(data), hash);
+    }
+
+    #[test]
+    fn test_verify_locked_checksum() {
+        let data = b"hello world";
+        let actual = sha256_hex(data);
+        assert!(verify_locked_checksum(
+            "B94D27B9934D3E08A52E52D7DA7DABFAC484EFE37A5380EE9088F7ACE2EFCDE9",
+            &actual,
+            "a.tar.gz"
+        )
+        .is_ok());
+
+        match verify_locked_checksum("deadbeef", &actual, "a.tar.gz") {
+            Err(DownloadError::LockedChecksumMismatch { asset, expected, .. }) => {
+                assert_eq!(asset, "a.tar.gz");
+                assert_eq!(expected, "deadbeef");
+            }
+            other => panic!("Expected LockedChecksumMismatch, got {:?}", other),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_extract_binary_from_archive_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("iii.tar.gz");
+
+        let encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&archive_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        let contents = b"#!/bin/sh\necho iii\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "iii-v1/iii", &contents[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let target = dir.path().join("bin").join("iii");
+        let hash = extract_binary("iii", &archive_path, &target).unwrap();
+        assert_eq!(hash, sha256_hex(contents));
+        assert_eq!(std::fs::read(&target).unwrap(), contents);
+        assert!(!target.with_extension("tmp").exists());
+
+        assert!(extract_binary("other", &archive_path, &dir.path().join("other")).is_err());
+        assert!(!dir.path().join("other.tmp").exists());
+    }
+
+    #[test]
+    fn test_resumable_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("iii.tar.gz.part");
+        let asset = ReleaseAsset {
+            name: "iii.tar.gz".to_string(),
+            browser_download_url: "https://example.com/iii.tar.gz".to_string(),
+            size: 100,
+            digest: None,
+        };
+        let meta = PartialDownload {
+            url: asset.browser_download_url.clone(),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            expected_size: 100,
+        };
+
+        // No metadata: the partial file cannot be trusted
+        std::fs::write(&partial, [0u8; 40]).unwrap();
+        assert!(resumable_offset(&asset, &partial).is_none());
+
+        meta.save(&partial_meta_path(&partial)).unwrap();
+        assert_eq!(resumable_offset(&asset, &partial), Some((40, meta.clone())));
+
+        // A different release asset at the same name starts over
+        let moved = ReleaseAsset {
+            browser_download_url: "https://example.com/v2/iii.tar.gz".to_string(),
+            ..asset.clone()
+        };
+        assert!(resumable_offset(&moved, &partial).is_none());
+
+        // Already complete: nothing left to resume
+        std::fs::write(&partial, [0u8; 100]).unwrap();
+        assert!(resumable_offset(&asset, &partial).is_none());
+    }
+
+    #[test]
+    fn test_partial_validator_skips_weak_etags() {
+        let mut meta = PartialDownload {
+            url: String::new(),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some("Wed, 01 Oct 2025 00:00:00 GMT".to_string()),
+            expected_size: 0,
+        };
+        assert_eq!(meta.validator(), Some("\"abc\""));
+        meta.etag = Some("W/\"abc\"".to_string());
+        assert_eq!(meta.validator(), Some("Wed, 01 Oct 2025 00:00:00 GMT"));
+        meta.last_modified = None;
+        assert_eq!(meta.validator(), None);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_response_is_a_read_idle_timeout() {
+        // Accept the connection but never answer
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/iii.tar.gz", listener.local_addr().unwrap());
+        let client = reqwest::Client::builder()
+            .read_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let error = client.get(&url).send().await.unwrap_err();
+        assert!(matches!(
+            timeout_of(&error),
+            Some(DownloadTimeout::ReadIdle(_))
+        ));
+        drop(listener);
+    }
+
+    /// Serve one canned response per connection, in order. Returns the
+    /// server's URL and a handle yielding the requests it received.
+    async fn serve(responses: Vec<String>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/iii.tar.gz", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    let mut buf = [0; 1024];
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                requests.push(String::from_utf8(request).unwrap().to_lowercase());
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\nConnection: close\r\n", status);
+        for header in headers {
+            response.push_str(&format!("{}\r\n", header));
+        }
+        format!("{}Content-Length: {}\r\n\r\n{}", response, body.len(), body)
+    }
+
+    /// A partial download of "hello world" holding its first 6 bytes.
+    fn interrupted(dir: &Path, url: &str) -> (ReleaseAsset, PathBuf) {
+        let asset = ReleaseAsset {
+            name: "iii.tar.gz".to_string(),
+            browser_download_url: url.to_string(),
+            size: 11,
+            digest: None,
+        };
+        let partial = dir.join("iii.tar.gz.part");
+        std::fs::write(&partial, b"hello ").unwrap();
+        PartialDownload {
+            url: url.to_string(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            expected_size: 11,
+        }
+        .save(&partial_meta_path(&partial))
+        .unwrap();
+        (asset, partial)
+    }
+
+    async fn attempt(asset: &ReleaseAsset, partial: &Path) -> String {
+        let mut retrier = Retrier::new(RetryPolicy {
+            retries: 0,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        });
+        let (path, hash) = download_attempt(&reqwest::Client::new(), asset, partial, &mut retrier)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"hello world");
+        hash
+    }
+
+    #[tokio::test]
+    async fn test_resume_appends_partial_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(vec![response(
+            "206 Partial Content",
+            &["Content-Range: bytes 6-10/11"],
+            "world",
+        )])
+        .await;
+        let (asset, partial) = interrupted(dir.path(), &url);
+
+        assert_eq!(attempt(&asset, &partial).await, sha256_hex(b"hello world"));
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("range: bytes=6-\r\n"));
+        assert!(requests[0].contains("if-range: \"v1\"\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_starts_over_when_range_does_not_match() {
+        // A range from the wrong offset must not be written as the whole file
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(vec![
+            response("206 Partial Content", &["Content-Range: bytes 4-10/11"], "o world"),
+            response("200 OK", &["ETag: \"v1\""], "hello world"),
+        ])
+        .await;
+        let (asset, partial) = interrupted(dir.path(), &url);
+
+        assert_eq!(attempt(&asset, &partial).await, sha256_hex(b"hello world"));
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("range: bytes=6-\r\n"));
+        assert!(!requests[1].contains("range:"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_starts_over_when_content_changed() {
+        // The validator no longer matches, so the server sends everything
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(vec![response(
+            "200 OK",
+            &["ETag: \"v2\""],
+            "hello world",
+        )])
+        .await;
+        let (asset, partial) = interrupted(dir.path(), &url);
+
+        assert_eq!(attempt(&asset, &partial).await, sha256_hex(b"hello world"));
+        server.await.unwrap();
+        let meta = PartialDownload::load(&partial_meta_path(&partial)).unwrap();
+        assert_eq!(meta.etag.as_deref(), Some("\"v2\""));
+    }
+
+    #[tokio::test]
+    async fn test_unsatisfiable_range_fetches_whole_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(vec![
+            response("416 Range Not Satisfiable", &[], ""),
+            response("200 OK", &["ETag: \"v1\""], "hello world"),
+        ])
+        .await;
+        let (asset, partial) = interrupted(dir.path(), &url);
+
+        assert_eq!(attempt(&asset, &partial).await, sha256_hex(b"hello world"));
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("range: bytes=6-"));
+        assert!(!requests[1].contains("range:"));
     }
 }
@@ -23,6 +23,18 @@ pub enum IiiCliError {
 
     #[error(transparent)]
     State(#[from] StateError),
+
+    #[error(transparent)]
+    Toolchain(#[from] ToolchainError),
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+
+    #[error(transparent)]
+    Lock(#[from] LockError),
 }
 
 #[derive(Error, Debug)]
@@ -30,7 +42,13 @@ pub enum NetworkError {
     #[error("HTTP request failed: {0}")]
     RequestFailed(#[from] reqwest::Error),
 
-    #[error("GitHub API rate limit exceeded. Set GITHUB_TOKEN or III_GITHUB_TOKEN environment variable for higher limits.")]
+    #[error("HTTP request failed after {attempts} attempts: {source}")]
+    RetriesExhausted {
+        attempts: u32,
+        source: reqwest::Error,
+    },
+
+    #[error("GitHub API rate limit exceeded. Set GITHUB_TOKEN or III_GITHUB_TOKEN, or run `iii-cli config set github.token <token>`, for higher limits.")]
     RateLimited,
 
     #[error("Release asset not found for platform {platform}: {binary}")]
@@ -49,8 +67,80 @@ pub enum DownloadError {
         actual: String,
     },
 
+    #[error("SHA256 of {asset} does not match the lockfile. Expected: {expected}, got: {actual}. The release asset may have been replaced since it was locked; refusing to install.")]
+    LockedChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("The lockfile entry for {binary} does not point at its release on GitHub ({reason}); refusing to download it.")]
+    UntrustedLockedArtifact { binary: String, reason: String },
+
+    #[error("SHA256 of {asset} differs from the hash recorded when it was first installed. Expected: {expected}, got: {actual}. The release asset may have been replaced; refusing to reinstall it.")]
+    RecordedChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
+
+    #[error("Download of {asset} timed out after {attempts} attempt(s): {timeout}")]
+    Timeout {
+        asset: String,
+        attempts: u32,
+        timeout: DownloadTimeout,
+    },
+
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+}
+
+impl DownloadError {
+    /// Whether the archive did not match its expected SHA256.
+    pub fn is_checksum_mismatch(&self) -> bool {
+        matches!(
+            self,
+            Self::ChecksumMismatch { .. }
+                | Self::LockedChecksumMismatch { .. }
+                | Self::RecordedChecksumMismatch { .. }
+        )
+    }
+}
+
+/// Which download timeout fired.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownloadTimeout {
+    Connect(std::time::Duration),
+    ReadIdle(std::time::Duration),
+    Total(std::time::Duration),
+}
+
+impl std::fmt::Display for DownloadTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connect(limit) => write!(
+                f,
+                "could not connect within {}s (download.connect_timeout_secs)",
+                limit.as_secs()
+            ),
+            Self::ReadIdle(limit) => write!(
+                f,
+                "no data received for {}s (download.read_timeout_secs)",
+                limit.as_secs()
+            ),
+            Self::Total(limit) => write!(
+                f,
+                "not finished within {}s (download.timeout_secs)",
+                limit.as_secs()
+            ),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -71,12 +161,27 @@ pub enum StorageError {
     },
 
     #[error("Failed to write file {path}: {source}")]
-    #[allow(dead_code)]
     WriteFile {
         path: String,
         source: std::io::Error,
     },
 
+    #[error("{path} {reason}. Re-run with --force to {action} it anyway.")]
+    NotOwned {
+        path: String,
+        reason: String,
+        action: String,
+    },
+
+    #[error("{binary} was not installed by iii-cli{location}. Refusing to remove it.")]
+    NotManaged { binary: String, location: String },
+
+    #[error("{binary} has no previous version to roll back to.")]
+    NoPreviousVersion { binary: String },
+
+    #[error("{binary} v{version} is not installed. Run 'iii-cli install {binary}@{version}' first.")]
+    VersionNotInstalled { binary: String, version: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -107,6 +212,27 @@ pub enum RegistryError {
 
     #[error("No releases found for {binary}. This binary may not yet be available for download.")]
     NoReleasesAvailable { binary: String },
+
+    #[error("No release found for {binary} v{version}. Check the version number and try again.")]
+    VersionNotFound { binary: String, version: String },
+
+    #[error("No stable release of {binary} matches '{requirement}'.")]
+    NoMatchingRelease { binary: String, requirement: String },
+
+    #[error("Failed to fetch registry manifest from {url}: {reason}")]
+    ManifestFetch { url: String, reason: String },
+
+    #[error("Registry manifest checksum mismatch. Expected: {expected}, got: {actual}. Keeping the cached registry.")]
+    ManifestChecksumMismatch { expected: String, actual: String },
+
+    #[error("Invalid registry manifest: {0}")]
+    ManifestInvalid(String),
+
+    #[error("Invalid version '{version}': {reason}")]
+    InvalidVersion { version: String, reason: String },
+
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
 }
 
 #[derive(Error, Debug)]
@@ -117,6 +243,132 @@ pub enum StateError {
     #[error("Failed to parse state file: {0}")]
     ParseFailed(#[from] serde_json::Error),
 
+    #[error("The state file was written by a newer iii-cli (schema version {found}; this version supports up to {supported}). Update iii-cli with the installer to change it.")]
+    NewerSchema { found: u32, supported: u32 },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
+
+#[derive(Error, Debug)]
+pub enum ToolchainError {
+    #[error("Failed to read toolchain file {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse toolchain file {path}: {message}")]
+    Parse { path: String, message: String },
+
+    #[error("Unknown tool '{tool}' in {path}. Expected one of: {known}")]
+    UnknownTool {
+        path: String,
+        tool: String,
+        known: String,
+    },
+
+    #[error("No {file} found in {dir} or any parent directory. {hint}")]
+    NotFound {
+        file: String,
+        dir: String,
+        hint: String,
+    },
+
+    #[error("Invalid version pin for {tool}: '{value}' ({reason})")]
+    InvalidPin {
+        tool: String,
+        value: String,
+        reason: String,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse config ({origin}): {message}")]
+    Parse { origin: String, message: String },
+
+    #[error("Invalid value for {key} ({origin}): {reason}")]
+    InvalidValue {
+        key: String,
+        origin: String,
+        reason: String,
+    },
+
+    #[error("{key} cannot be set in a project config file ({origin}). Set it in the user or system config, or with an environment variable.")]
+    NotAllowedInProject { key: String, origin: String },
+
+    #[error("Unknown config key '{key}'. Expected one of: {known}")]
+    UnknownKey { key: String, known: String },
+
+    #[error("Failed to write config file {path}: {source}")]
+    Write {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum PolicyError {
+    #[error("Failed to read policy file {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse policy file {path}: {message}")]
+    Parse { path: String, message: String },
+
+    #[error("Invalid forbidden version range for {tool}: '{range}' ({reason})")]
+    InvalidRange {
+        tool: String,
+        range: String,
+        reason: String,
+    },
+
+    #[error("{binary} comes from {repo}, which is not an allowed repository under the policy in {policy}. Contact your administrator.")]
+    RepoNotAllowed {
+        binary: String,
+        repo: String,
+        policy: String,
+    },
+
+    #[error("Downloading from {url} is not allowed by the policy in {policy}. Contact your administrator.")]
+    MirrorNotAllowed { url: String, policy: String },
+
+    #[error("{binary} has no checksum to verify, and the policy in {policy} requires checksums. Refusing to install.")]
+    ChecksumRequired { binary: String, policy: String },
+
+    #[error("{binary} v{version} is forbidden by the policy in {policy} (matches {range}). Contact your administrator.")]
+    VersionForbidden {
+        binary: String,
+        version: semver::Version,
+        range: String,
+        policy: String,
+    },
+
+    #[error("Self-update is disabled by the policy in {policy}. Update iii-cli through your administrator.")]
+    SelfUpdateDisabled { policy: String },
+}
+
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("Failed to lock {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Timed out after {secs}s waiting for another iii-cli process{holder} to release {path}. Raise lock.timeout_secs to wait longer.")]
+    Timeout {
+        path: String,
+        secs: u64,
+        holder: String,
+    },
+}
@@ -1,7 +1,15 @@
+use std::io::Read;
 use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use semver::Version;
 
 use crate::error::ExecError;
 
+/// How long `probe_version` waits for `<binary> --version`.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Run a binary with the given arguments.
 ///
 /// On Unix: Uses the POSIX process-replacement syscall to hand off the
@@ -80,6 +88,44 @@ fn run_binary_windows(binary_path: &Path, args: &[String]) -> Result<i32, ExecEr
     Ok(status.code().unwrap_or(1))
 }
 
+/// Ask a binary for its version by running `<binary> --version` and taking
+/// the first word of the output that parses as a version ("iii 0.3.1",
+/// "v0.3.1"). The binary is killed if it does not exit within a few seconds.
+pub fn probe_version(binary_path: &Path) -> Option<Version> {
+    let mut child = Command::new(binary_path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break,
+            Ok(Some(_)) | Err(_) => return None,
+            Ok(None) if started.elapsed() >= PROBE_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+        }
+    }
+
+    let mut output = String::new();
+    child.stdout.take()?.read_to_string(&mut output).ok()?;
+    parse_version_output(&output)
+}
+
+fn parse_version_output(output: &str) -> Option<Version> {
+    output.split_whitespace().find_map(|word| {
+        let word = word.strip_prefix('v').unwrap_or(word);
+        Version::parse(word).ok()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,6 +143,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(parse_version_output("iii 0.3.1\n"), Some(Version::new(0, 3, 1)));
+        assert_eq!(parse_version_output("v1.2.0"), Some(Version::new(1, 2, 0)));
+        assert_eq!(parse_version_output("iii-console (dev build)"), None);
+    }
+
     #[test]
     fn test_flush_output_no_panic() {
         flush_output();
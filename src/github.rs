@@ -1,48 +1,87 @@
 use serde::Deserialize;
 use semver::Version;
 
+use crate::config;
 use crate::error::{NetworkError, RegistryError};
 use crate::registry::BinarySpec;
+use crate::retry;
 
-/// A GitHub release from the /releases/latest endpoint.
-#[derive(Debug, Deserialize)]
+/// A GitHub release from the /releases, /releases/latest or /releases/tags endpoint.
+#[derive(Debug, Clone, Deserialize)]
 pub struct Release {
     pub tag_name: String,
     pub assets: Vec<ReleaseAsset>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
 }
 
 /// A single asset in a GitHub release.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ReleaseAsset {
     pub name: String,
     pub browser_download_url: String,
     pub size: u64,
+    /// Content digest computed by GitHub (e.g., "sha256:ab12..."), when available
+    #[serde(default)]
+    pub digest: Option<String>,
 }
 
-/// Build an HTTP client with proper configuration.
+impl ReleaseAsset {
+    /// The SHA256 GitHub computed for this asset, if it reports one.
+    pub fn sha256_digest(&self) -> Option<&str> {
+        self.digest.as_deref().and_then(|d| d.strip_prefix("sha256:"))
+    }
+}
+
+/// Build an HTTP client for API calls, with a short total timeout.
 pub fn build_client() -> Result<reqwest::Client, reqwest::Error> {
-    let mut builder = reqwest::Client::builder()
-        .user_agent(format!("iii-cli/{}", env!("CARGO_PKG_VERSION")))
-        .timeout(std::time::Duration::from_secs(30));
-
-    // Support optional GitHub token for higher rate limits
-    if let Some(token) = github_token() {
-        use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
-        let mut headers = HeaderMap::new();
-        if let Ok(val) = HeaderValue::from_str(&format!("token {}", token)) {
-            headers.insert(AUTHORIZATION, val);
+    client_builder()
+        .timeout(config::current().network_timeout())
+        .build()
+}
+
+/// Build an HTTP client for downloading release assets. Large archives on
+/// slow connections may take a long time, so instead of a total timeout
+/// connecting and each read are limited separately.
+pub fn build_download_client() -> Result<reqwest::Client, reqwest::Error> {
+    let config = config::current();
+    client_builder()
+        .connect_timeout(config.download_connect_timeout())
+        .read_timeout(config.download_read_timeout())
+        .build()
+}
+
+fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder().user_agent(format!("iii-cli/{}", env!("CARGO_PKG_VERSION")))
+}
+
+/// Hosts the GitHub token is sent to.
+const TOKEN_HOSTS: &[&str] = &["api.github.com", "github.com"];
+
+/// Start a GET request, with the GitHub token for higher rate limits when
+/// one is set and `url` is a GitHub URL. The token is never sent to other
+/// hosts (mirrors, registry or advisory URLs); reqwest also drops it when a
+/// release download redirects to another host.
+pub fn get(client: &reqwest::Client, url: &str) -> reqwest::RequestBuilder {
+    let request = client.get(url);
+    match config::current().github_token() {
+        Some(token) if is_token_host(url) => {
+            request.header(reqwest::header::AUTHORIZATION, format!("token {}", token))
         }
-        builder = builder.default_headers(headers);
+        _ => request,
     }
-
-    builder.build()
 }
 
-/// Get the GitHub token from environment variables.
-fn github_token() -> Option<String> {
-    std::env::var("III_GITHUB_TOKEN")
-        .or_else(|_| std::env::var("GITHUB_TOKEN"))
-        .ok()
+/// Whether `url` is an https URL on a host the token may be sent to.
+fn is_token_host(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| {
+        url.scheme() == "https"
+            && url
+                .host_str()
+                .is_some_and(|host| TOKEN_HOSTS.contains(&host))
+    })
 }
 
 /// Fetch the latest stable release for a binary.
@@ -58,8 +97,122 @@ pub async fn fetch_latest_release(
         spec.repo
     );
 
-    let response = client.get(&url).send().await?;
+    let response = retry::send(get(client, &url)).await?;
+
+    parse_release_response(response, || RegistryError::NoReleasesAvailable {
+        binary: spec.name.to_string(),
+    })
+    .await
+}
+
+/// Fetch the release for a specific version of a binary.
+///
+/// Release tags are usually prefixed with 'v', but some repos tag bare
+/// versions, so both forms are tried before giving up.
+pub async fn fetch_release_by_version(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    version: &Version,
+) -> Result<Release, IiiGithubError> {
+    for tag in [format!("v{}", version), version.to_string()] {
+        let url = format!(
+            "https://api.github.com/repos/{}/releases/tags/{}",
+            spec.repo, tag
+        );
+
+        let response = retry::send(get(client, &url)).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            continue;
+        }
+
+        return parse_release_response(response, || RegistryError::VersionNotFound {
+            binary: spec.name.to_string(),
+            version: version.to_string(),
+        })
+        .await;
+    }
+
+    Err(IiiGithubError::Registry(RegistryError::VersionNotFound {
+        binary: spec.name.to_string(),
+        version: version.to_string(),
+    }))
+}
+
+/// Fetch the most recent releases for a binary (newest first, up to 100).
+pub async fn fetch_releases(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+) -> Result<Vec<Release>, IiiGithubError> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases?per_page=100",
+        spec.repo
+    );
+
+    let response = retry::send(get(client, &url)).await?;
 
+    match response.status() {
+        status if status.is_success() => Ok(response.json().await?),
+        status if status == reqwest::StatusCode::FORBIDDEN => {
+            Err(IiiGithubError::Network(NetworkError::RateLimited))
+        }
+        status if status == reqwest::StatusCode::NOT_FOUND => {
+            Err(IiiGithubError::Registry(RegistryError::NoReleasesAvailable {
+                binary: spec.name.to_string(),
+            }))
+        }
+        _status => Err(IiiGithubError::Network(NetworkError::RequestFailed(
+            response.error_for_status().unwrap_err(),
+        ))),
+    }
+}
+
+/// Core API rate limit for the current client (token or anonymous).
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Unix timestamp when the limit resets
+    pub reset: i64,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResponse {
+    resources: RateLimitResources,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResources {
+    core: RateLimit,
+}
+
+/// Fetch the core API rate limit. Querying it does not count against it.
+pub async fn fetch_rate_limit(client: &reqwest::Client) -> Result<RateLimit, IiiGithubError> {
+    let response = retry::send(get(client, "https://api.github.com/rate_limit"))
+        .await?
+        .error_for_status()?;
+    let body: RateLimitResponse = response.json().await?;
+    Ok(body.resources.core)
+}
+
+/// Pick the highest stable release whose version satisfies `matches`.
+/// Drafts, pre-releases and tags that are not valid semver are skipped.
+pub fn find_matching_release(
+    releases: &[Release],
+    matches: impl Fn(&Version) -> bool,
+) -> Option<(Version, &Release)> {
+    releases
+        .iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter_map(|r| parse_release_version(&r.tag_name).ok().map(|v| (v, r)))
+        .filter(|(v, _)| matches(v))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+}
+
+/// Map a releases API response to a Release or the matching error.
+async fn parse_release_response(
+    response: reqwest::Response,
+    not_found: impl FnOnce() -> RegistryError,
+) -> Result<Release, IiiGithubError> {
     match response.status() {
         status if status.is_success() => {
             let release: Release = response.json().await?;
@@ -69,9 +222,7 @@ pub async fn fetch_latest_release(
             Err(IiiGithubError::Network(NetworkError::RateLimited))
         }
         status if status == reqwest::StatusCode::NOT_FOUND => {
-            Err(IiiGithubError::Registry(RegistryError::NoReleasesAvailable {
-                binary: spec.name.to_string(),
-            }))
+            Err(IiiGithubError::Registry(not_found()))
         }
         _status => {
             Err(IiiGithubError::Network(NetworkError::RequestFailed(
@@ -97,6 +248,14 @@ pub fn find_asset<'a>(release: &'a Release, asset_name: &str) -> Option<&'a Rele
     release.assets.iter().find(|a| a.name == asset_name)
 }
 
+/// Download URL of a release asset on GitHub.
+pub fn release_asset_url(repo: &str, tag: &str, asset_name: &str) -> String {
+    format!(
+        "https://github.com/{}/releases/download/{}/{}",
+        repo, tag, asset_name
+    )
+}
+
 /// Parse a version from a release tag (strips leading 'v' if present).
 pub fn parse_release_version(tag: &str) -> Result<Version, semver::Error> {
     let cleaned = tag.strip_prefix('v').unwrap_or(tag);
@@ -118,16 +277,20 @@ mod tests {
     fn test_find_asset() {
         let release = Release {
             tag_name: "v0.2.4".to_string(),
+            draft: false,
+            prerelease: false,
             assets: vec![
                 ReleaseAsset {
                     name: "iii-console-aarch64-apple-darwin.tar.gz".to_string(),
                     browser_download_url: "https://example.com/a".to_string(),
                     size: 1000,
+                    digest: Some("sha256:abc123".to_string()),
                 },
                 ReleaseAsset {
                     name: "iii-console-x86_64-apple-darwin.tar.gz".to_string(),
                     browser_download_url: "https://example.com/b".to_string(),
                     size: 2000,
+                    digest: None,
                 },
             ],
         };
@@ -136,14 +299,56 @@ mod tests {
         assert!(found.is_some());
         assert_eq!(found.unwrap().browser_download_url, "https://example.com/a");
 
+        assert_eq!(found.unwrap().sha256_digest(), Some("abc123"));
+
         let not_found = find_asset(&release, "nonexistent.tar.gz");
         assert!(not_found.is_none());
     }
 
+    fn release(tag: &str, prerelease: bool) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            assets: Vec::new(),
+            draft: false,
+            prerelease,
+        }
+    }
+
+    #[test]
+    fn test_find_matching_release() {
+        let releases = vec![
+            release("v0.5.0-rc.1", true),
+            release("v0.4.3", false),
+            release("v0.3.9", false),
+            release("not-a-version", false),
+        ];
+        let req = semver::VersionReq::parse("^0.3").unwrap();
+        let (version, found) = find_matching_release(&releases, |v| req.matches(v)).unwrap();
+        assert_eq!(version, Version::new(0, 3, 9));
+        assert_eq!(found.tag_name, "v0.3.9");
+
+        let (version, _) = find_matching_release(&releases, |_| true).unwrap();
+        assert_eq!(version, Version::new(0, 4, 3), "pre-releases must be skipped");
+
+        assert!(find_matching_release(&releases, |v| v.major == 9).is_none());
+    }
+
+    #[test]
+    fn test_token_is_only_sent_to_github() {
+        assert!(is_token_host("https://api.github.com/repos/iii-hq/iii/releases/latest"));
+        assert!(is_token_host(
+            "https://github.com/iii-hq/iii/releases/download/v0.4.2/iii.tar.gz"
+        ));
+        assert!(!is_token_host("http://api.github.com/rate_limit"));
+        assert!(!is_token_host("https://github.com.evil.net/iii.tar.gz"));
+        assert!(!is_token_host("https://raw.githubusercontent.com/iii-hq/iii-cli/main/registry.json"));
+        assert!(!is_token_host("https://mirror.acme.internal/iii.tar.gz"));
+    }
+
     #[test]
-    fn test_github_token_not_set() {
-        // In test environment, token is typically not set
-        // This just exercises the function
-        let _ = github_token();
+    fn test_build_client_with_default_config() {
+        // The token and timeout come from config; defaults must build a client
+        assert!(build_client().is_ok());
+        assert!(build_download_client().is_ok());
     }
 }
@@ -0,0 +1,212 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::download::ChecksumStatus;
+use crate::{format, platform};
+
+/// What changed in one history entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Install,
+    Update,
+    /// Switched to a version already in the store (`iii-cli use`)
+    Switch,
+    Rollback,
+    Repair,
+    Uninstall,
+}
+
+impl Action {
+    /// Install when nothing was active before, update otherwise.
+    pub fn for_change(from: Option<&Version>) -> Self {
+        match from {
+            Some(_) => Self::Update,
+            None => Self::Install,
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Install => "install",
+            Self::Update => "update",
+            Self::Switch => "switch",
+            Self::Rollback => "rollback",
+            Self::Repair => "repair",
+            Self::Uninstall => "uninstall",
+        };
+        f.pad(s)
+    }
+}
+
+/// One line of the history log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: DateTime<Utc>,
+    pub action: Action,
+    pub binary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<Version>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Version>,
+    /// Release asset that was downloaded (None when nothing was downloaded)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<ChecksumStatus>,
+    pub duration_ms: u64,
+}
+
+impl Entry {
+    /// Start an entry for an operation that began at `started`.
+    pub fn new(action: Action, binary: &str, started: Instant) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action,
+            binary: binary.to_string(),
+            from: None,
+            to: None,
+            asset: None,
+            checksum: None,
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// Record the versions before and after the change.
+    pub fn versions(mut self, from: Option<Version>, to: Option<Version>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    /// Record the downloaded asset and how it was verified.
+    pub fn download(mut self, asset: String, checksum: ChecksumStatus) -> Self {
+        self.asset = Some(asset);
+        self.checksum = Some(checksum);
+        self
+    }
+}
+
+/// Append an entry to the history log. History is informational, so a
+/// failure to write it is reported but does not fail the operation.
+pub fn record(entry: Entry) {
+    if let Err(e) = append(&platform::history_file_path(), &entry) {
+        eprintln!(
+            "  {} Failed to write install history: {}",
+            "warning:".yellow(),
+            e
+        );
+    }
+}
+
+/// Append one JSON line to the log at `path`.
+pub fn append(path: &Path, entry: &Entry) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    // A single append-mode write keeps lines from concurrent processes whole
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Read the log at `path`, oldest first. Lines that do not parse (written
+/// by a newer iii-cli, or cut off) are skipped.
+pub fn read(path: &Path) -> std::io::Result<Vec<Entry>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Print history entries, one per line.
+pub fn print_entries(entries: &[Entry]) {
+    for entry in entries {
+        let time = entry.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+        let download = match (&entry.asset, entry.checksum) {
+            (Some(asset), Some(checksum)) => format!("{} (checksum {})", asset, checksum),
+            _ => String::new(),
+        };
+        eprintln!(
+            "  {}  {:<9}  {:<16}  {:<18}  {:>6}  {}",
+            time.to_string().dimmed(),
+            entry.action,
+            entry.binary,
+            change(entry),
+            format::format_duration(Duration::from_millis(entry.duration_ms)),
+            download.dimmed()
+        );
+    }
+}
+
+/// "0.3.1 → 0.3.2", "→ 0.3.2" for installs, "0.3.1 →" for uninstalls.
+fn change(entry: &Entry) -> String {
+    match (&entry.from, &entry.to) {
+        (Some(from), Some(to)) if from == to => to.to_string(),
+        (Some(from), Some(to)) => format!("{} → {}", from, to),
+        (None, Some(to)) => format!("→ {}", to),
+        (Some(from), None) => format!("{} →", from),
+        (None, None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert!(read(&path).unwrap().is_empty());
+
+        let install = Entry::new(Action::Install, "iii", Instant::now())
+            .versions(None, Some(Version::new(0, 4, 2)))
+            .download("iii.tar.gz".to_string(), ChecksumStatus::Verified);
+        let uninstall = Entry::new(Action::Uninstall, "iii", Instant::now())
+            .versions(Some(Version::new(0, 4, 2)), None);
+        append(&path, &install).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"truncated\n")
+            .unwrap();
+        append(&path, &uninstall).unwrap();
+
+        assert_eq!(read(&path).unwrap(), vec![install, uninstall]);
+    }
+
+    #[test]
+    fn test_change() {
+        let entry = |from: Option<Version>, to: Option<Version>| {
+            Entry::new(Action::Update, "iii", Instant::now()).versions(from, to)
+        };
+        assert_eq!(
+            change(&entry(Some(Version::new(0, 3, 1)), Some(Version::new(0, 3, 2)))),
+            "0.3.1 → 0.3.2"
+        );
+        assert_eq!(change(&entry(None, Some(Version::new(0, 3, 2)))), "→ 0.3.2");
+        assert_eq!(change(&entry(Some(Version::new(0, 3, 1)), None)), "0.3.1 →");
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::error::LockError;
+use crate::platform;
+
+/// File in data_dir() that serializes state changes and installs across
+/// iii-cli processes.
+pub const LOCK_FILE: &str = "iii-cli.lock";
+
+/// How often a waiting process retries the lock.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An exclusive advisory lock, released when dropped.
+#[derive(Debug)]
+pub struct DataLock {
+    _file: File,
+}
+
+/// Path of the lock file.
+pub fn lock_path() -> PathBuf {
+    platform::data_dir().join(LOCK_FILE)
+}
+
+/// Lock the data directory before loading state that will be saved again or
+/// installing binaries, waiting up to `lock.timeout_secs` for other processes.
+pub fn lock_data_dir() -> Result<DataLock, LockError> {
+    acquire(&lock_path(), config::current().lock_timeout())
+}
+
+/// Lock the data directory only if no other process holds it.
+pub fn try_lock_data_dir() -> Result<Option<DataLock>, LockError> {
+    let path = lock_path();
+    let file = open(&path)?;
+    match file.try_lock() {
+        Ok(()) => claim(file, &path).map(Some),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(io_error(&path, e)),
+    }
+}
+
+/// Take an exclusive lock on `path`, waiting up to `timeout` for the process
+/// holding it. A message is printed once if the lock is busy.
+pub fn acquire(path: &Path, timeout: Duration) -> Result<DataLock, LockError> {
+    let file = open(path)?;
+    let started = Instant::now();
+    let mut announced = false;
+
+    loop {
+        match file.try_lock() {
+            Ok(()) => return claim(file, path),
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => return Err(io_error(path, e)),
+        }
+
+        if started.elapsed() >= timeout {
+            return Err(LockError::Timeout {
+                path: path.display().to_string(),
+                secs: timeout.as_secs(),
+                holder: holder_description(path),
+            });
+        }
+        if !announced {
+            eprintln!(
+                "  Waiting for another iii-cli process{} to finish...",
+                holder_description(path)
+            );
+            announced = true;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn open(path: &Path) -> Result<File, LockError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(path, e))?;
+    }
+    // Not truncated on open: that would erase the holder's pid
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| io_error(path, e))
+}
+
+/// Record our pid in the lock file so waiting processes can name the holder.
+fn claim(mut file: File, path: &Path) -> Result<DataLock, LockError> {
+    let write = |file: &mut File| -> std::io::Result<()> {
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()
+    };
+    write(&mut file).map_err(|e| io_error(path, e))?;
+    Ok(DataLock { _file: file })
+}
+
+/// " (pid N)" for the process holding the lock, if it can be read.
+fn holder_description(path: &Path) -> String {
+    let mut content = String::new();
+    let pid = File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .ok()
+        .and_then(|_| content.trim().parse::<u32>().ok());
+    pid.map(|pid| format!(" (pid {})", pid)).unwrap_or_default()
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> LockError {
+    LockError::Io {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCK_FILE);
+
+        let held = acquire(&path, Duration::ZERO).unwrap();
+        assert_eq!(holder_description(&path), format!(" (pid {})", std::process::id()));
+        assert!(matches!(
+            acquire(&path, Duration::ZERO),
+            Err(LockError::Timeout { .. })
+        ));
+
+        drop(held);
+        assert!(acquire(&path, Duration::ZERO).is_ok());
+    }
+}
@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::download::{self, DownloadAndInstallError};
+use crate::error::{StorageError, ToolchainError};
+use crate::github::{self, Release};
+use crate::platform;
+use crate::registry::{self, BinarySpec};
+use crate::toolchain::Toolchain;
+use crate::update::{self, UpdateError};
+
+/// File name of the lockfile written by `iii-cli lock`, next to iii-toolchain.toml.
+pub const LOCKFILE_NAME: &str = "iii-toolchain.lock";
+
+/// Current lockfile format version.
+const LOCKFILE_VERSION: u32 = 1;
+
+const LOCKFILE_HEADER: &str =
+    "# This file is generated by `iii-cli lock`. Do not edit it by hand.\n\n";
+
+/// Resolved versions and archive hashes for every managed tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Lockfile format version
+    pub version: u32,
+
+    /// Locked tools, one entry per binary
+    #[serde(default, rename = "tool")]
+    pub tools: Vec<LockedTool>,
+}
+
+/// A single tool resolved to an exact release.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedTool {
+    /// Binary name (e.g., "iii-console")
+    pub name: String,
+
+    /// Exact resolved version
+    pub version: Version,
+
+    /// Release tag the version was resolved from
+    pub tag: String,
+
+    /// Release archives for each supported target
+    #[serde(default, rename = "artifact")]
+    pub artifacts: Vec<LockedArtifact>,
+}
+
+/// A release archive for one target triple.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedArtifact {
+    /// Target triple (e.g., "aarch64-apple-darwin")
+    pub target: String,
+
+    /// Release asset file name
+    pub asset: String,
+
+    /// Download URL of the asset
+    pub url: String,
+
+    /// SHA256 of the archive, lowercase hex
+    pub sha256: String,
+
+    /// Archive size in bytes (0 in lockfiles written before it was recorded)
+    #[serde(default)]
+    pub size: u64,
+}
+
+impl Lockfile {
+    /// Find and load the nearest lockfile, searching upward from `start`.
+    /// Returns Ok(None) if no lockfile exists.
+    pub fn discover(start: &Path) -> Result<Option<Self>, ToolchainError> {
+        match start
+            .ancestors()
+            .map(|dir| dir.join(LOCKFILE_NAME))
+            .find(|candidate| candidate.is_file())
+        {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Load a lockfile from disk.
+    pub fn load(path: &Path) -> Result<Self, ToolchainError> {
+        let content = std::fs::read_to_string(path).map_err(|e| ToolchainError::Read {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        toml::from_str(&content).map_err(|e| ToolchainError::Parse {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Write the lockfile to disk.
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let body = toml::to_string_pretty(self)
+            .map_err(|e| StorageError::Io(std::io::Error::other(e)))?;
+        std::fs::write(path, format!("{}{}", LOCKFILE_HEADER, body)).map_err(|e| {
+            StorageError::WriteFile {
+                path: path.display().to_string(),
+                source: e,
+            }
+        })
+    }
+
+    /// Get the locked entry for a binary, if any.
+    pub fn tool(&self, binary_name: &str) -> Option<&LockedTool> {
+        self.tools.iter().find(|t| t.name == binary_name)
+    }
+}
+
+impl LockedTool {
+    /// Get the archive locked for a target triple, if any.
+    pub fn artifact_for(&self, target: &str) -> Option<&LockedArtifact> {
+        self.artifacts.iter().find(|a| a.target == target)
+    }
+}
+
+/// Where `iii-cli lock` writes the lockfile: next to the toolchain file if
+/// there is one, otherwise in the current directory.
+pub fn lockfile_path(cwd: &Path, toolchain: Option<&Toolchain>) -> PathBuf {
+    toolchain
+        .and_then(|t| t.path.parent())
+        .unwrap_or(cwd)
+        .join(LOCKFILE_NAME)
+}
+
+/// Resolve every registry tool (honoring toolchain pins, otherwise latest) and
+/// record the archive hash for each supported target.
+pub async fn generate(
+    client: &reqwest::Client,
+    toolchain: Option<&Toolchain>,
+) -> Result<Lockfile, UpdateError> {
+    let mut tools = Vec::new();
+
+    for spec in registry::all_binaries() {
+        let (version, release) = match toolchain.and_then(|t| t.pin_for(spec.name)) {
+            Some(pin) => update::resolve_pinned_release(client, spec, pin).await?,
+            None => {
+                let release = github::fetch_latest_release(client, spec).await?;
+                let version = github::parse_release_version(&release.tag_name)
+                    .map_err(|e| UpdateError::VersionParse(e.to_string()))?;
+                (version, release)
+            }
+        };
+
+        eprintln!("  Locking {} v{}...", spec.name, version);
+        let artifacts = lock_artifacts(client, spec, &release).await?;
+
+        tools.push(LockedTool {
+            name: spec.name.to_string(),
+            version,
+            tag: release.tag_name.clone(),
+            artifacts,
+        });
+    }
+
+    Ok(Lockfile {
+        version: LOCKFILE_VERSION,
+        tools,
+    })
+}
+
+/// Record the asset and SHA256 of a release for each of the spec's targets.
+///
+/// The hash GitHub reports for the asset is used when present, then the
+/// `.sha256` sidecar; otherwise the archive is downloaded and hashed.
+async fn lock_artifacts(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    release: &Release,
+) -> Result<Vec<LockedArtifact>, UpdateError> {
+    let mut artifacts = Vec::new();
+
+    for target in spec.supported_targets {
+        let asset_name = platform::asset_name_for_target(spec.name, target);
+        let asset = match github::find_asset(release, &asset_name) {
+            Some(a) => a,
+            None => {
+                eprintln!(
+                    "  {} {} {} has no asset for {}, skipping",
+                    "warning:".yellow(),
+                    spec.name,
+                    release.tag_name,
+                    target
+                );
+                continue;
+            }
+        };
+
+        let sidecar = if spec.has_checksum {
+            let checksum_name = platform::checksum_asset_name_for_target(spec.name, target);
+            github::find_asset(release, &checksum_name)
+        } else {
+            None
+        };
+
+        let sha256 = match (asset.sha256_digest(), sidecar) {
+            (Some(digest), _) => digest.to_lowercase(),
+            (None, Some(sidecar)) => {
+                download::fetch_sidecar_checksum(client, &sidecar.browser_download_url)
+                    .await
+                    .map_err(DownloadAndInstallError::from)?
+            }
+            (None, None) => download::download_sha256(asset)
+                .await
+                .map_err(DownloadAndInstallError::from)?,
+        };
+
+        artifacts.push(LockedArtifact {
+            target: target.to_string(),
+            asset: asset_name,
+            url: asset.browser_download_url.clone(),
+            sha256,
+            size: asset.size,
+        });
+    }
+
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Lockfile {
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            tools: vec![LockedTool {
+                name: "iii".to_string(),
+                version: Version::new(0, 4, 2),
+                tag: "v0.4.2".to_string(),
+                artifacts: vec![LockedArtifact {
+                    target: "aarch64-apple-darwin".to_string(),
+                    asset: "iii-aarch64-apple-darwin.tar.gz".to_string(),
+                    url: "https://example.com/iii-aarch64-apple-darwin.tar.gz".to_string(),
+                    sha256: "abc123".to_string(),
+                    size: 1024,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+
+        sample().save(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# This file is generated"));
+
+        let loaded = Lockfile::load(&path).unwrap();
+        let tool = loaded.tool("iii").unwrap();
+        assert_eq!(tool.version, Version::new(0, 4, 2));
+        assert_eq!(
+            tool.artifact_for("aarch64-apple-darwin").unwrap().sha256,
+            "abc123"
+        );
+        assert!(tool.artifact_for("x86_64-pc-windows-msvc").is_none());
+        assert!(loaded.tool("iii-console").is_none());
+    }
+
+    #[test]
+    fn test_discover_searches_upward() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        sample().save(&dir.path().join(LOCKFILE_NAME)).unwrap();
+
+        let found = Lockfile::discover(&nested).unwrap().unwrap();
+        assert_eq!(found.tools.len(), 1);
+    }
+
+    #[test]
+    fn test_lockfile_path_next_to_toolchain() {
+        let toolchain = Toolchain::parse(Path::new("/repo/iii-toolchain.toml"), "").unwrap();
+        assert_eq!(
+            lockfile_path(Path::new("/repo/services"), Some(&toolchain)),
+            Path::new("/repo").join(LOCKFILE_NAME)
+        );
+        assert_eq!(
+            lockfile_path(Path::new("/repo/services"), None),
+            Path::new("/repo/services").join(LOCKFILE_NAME)
+        );
+    }
+}
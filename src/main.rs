@@ -1,44 +1,129 @@
 mod advisory;
+mod cache;
 mod cli;
+mod config;
+mod doctor;
 mod download;
 mod error;
 mod exec;
+mod format;
 mod github;
+mod history;
+mod lock;
+mod lockfile;
+mod ownership;
 mod platform;
+mod plugin;
+mod policy;
 mod registry;
+mod remote_registry;
+mod retry;
 mod state;
+mod store;
+mod toolchain;
 mod update;
+mod verify;
 
 use std::process;
 
-use clap::Parser;
+use clap::FromArgMatches;
 use colored::Colorize;
 
 use cli::{Cli, CommandInfo};
 
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
-    let exit_code = run(cli).await;
+    // Load layered config before anything reads settings
+    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let (config, errors) = config::Config::load(&cwd);
+    for e in errors {
+        eprintln!("{} {} (settings from there are ignored)", "warning:".yellow(), e);
+    }
+    config::init(config);
+
+    // Merge the cached remote registry manifest over the compiled-in registry
+    // before building the command tree, so new tools get subcommands.
+    registry::init(&remote_registry::load_cached());
+
+    // An unreadable policy must not silently lift its restrictions
+    match policy::Policy::load(&platform::policy_path()) {
+        Ok(p) => policy::init(p),
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            process::exit(1);
+        }
+    }
+    let args = config::current().expand_aliases(std::env::args_os().collect(), cli::is_reserved);
+
+    // Scanning PATH is only needed when the command may be a plugin
+    let plugins = if cli::needs_plugins(&args) {
+        plugin::discover()
+    } else {
+        Vec::new()
+    };
+    let matches = cli::build_command(&plugins).get_matches_from(args);
+    let exit_code = if let Some((mapping, args)) = cli::dispatch_target(&matches) {
+        handle_dispatch(mapping, &args, matches.get_flag("no_update_check")).await
+    } else if let Some((plugin, args)) = cli::plugin_target(&matches, &plugins) {
+        handle_plugin(plugin, &args)
+    } else {
+        let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        run(cli, &plugins).await
+    };
     process::exit(exit_code);
 }
 
-async fn run(cli: Cli) -> i32 {
+async fn run(cli: Cli, plugins: &[plugin::Plugin]) -> i32 {
     let cmd_info = cli::extract_command_info(&cli.command);
 
     match cmd_info {
-        CommandInfo::Dispatch { command, args } => {
-            handle_dispatch(command, args, cli.no_update_check).await
+        CommandInfo::Install { target, force } => handle_install(target, force).await,
+        CommandInfo::Update {
+            target,
+            version,
+            force,
+        } => handle_update(target, version, force).await,
+        CommandInfo::Uninstall { target, force } => handle_uninstall(target, force),
+        CommandInfo::Lock => handle_lock().await,
+        CommandInfo::Sync { locked } => handle_sync(locked).await,
+        CommandInfo::Use { target, force } => handle_use(target, force),
+        CommandInfo::List => handle_list(plugins),
+        CommandInfo::Rollback { target, force } => handle_rollback(target, force),
+        CommandInfo::History { target } => handle_history(target),
+        CommandInfo::Verify { target, repair } => handle_verify(target, repair).await,
+        CommandInfo::Doctor { json } => handle_doctor(json).await,
+        CommandInfo::ConfigGet { key, show_origin } => handle_config_get(key, show_origin),
+        CommandInfo::ConfigSet { key, value, scope } => handle_config_set(key, value, scope),
+        CommandInfo::ConfigList { show_origin } => handle_config_list(show_origin),
+        CommandInfo::CacheList => handle_cache_list(),
+        CommandInfo::CacheClean => handle_cache_clean(),
+        CommandInfo::CacheSize => handle_cache_size(),
+    }
+}
+
+/// Handle running an external `iii-cli-<name>` plugin.
+///
+/// Plugins are not managed, so there is nothing to install or check for updates.
+fn handle_plugin(plugin: &plugin::Plugin, args: &[String]) -> i32 {
+    match exec::run_binary(&plugin.path, args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            1
         }
-        CommandInfo::Update { target } => handle_update(target).await,
-        CommandInfo::List => handle_list(),
     }
 }
 
 /// Handle dispatching a command to a managed binary.
-async fn handle_dispatch(command: &str, args: &[String], no_update_check: bool) -> i32 {
+async fn handle_dispatch(
+    mapping: &registry::CommandMapping,
+    args: &[String],
+    no_update_check: bool,
+) -> i32 {
     // Resolve command to binary spec
-    let (spec, binary_subcommand) = match registry::resolve_command(command) {
+    let command = mapping.path();
+    let resolved = registry::resolve_command(mapping.namespace, mapping.cli_command);
+    let (spec, binary_subcommand) = match resolved {
         Ok(result) => result,
         Err(e) => {
             eprintln!("{} {}", "error:".red(), e);
@@ -46,11 +131,15 @@ async fn handle_dispatch(command: &str, args: &[String], no_update_check: bool)
         }
     };
 
-    // Check platform support early
+    // Check platform support and policy early
     if let Err(e) = platform::check_platform_support(spec) {
         eprintln!("{} {}", "error:".red(), e);
         return 1;
     }
+    if let Err(e) = policy::current().check_repo(spec) {
+        eprintln!("{} {}", "error:".red(), e);
+        return 1;
+    }
 
     // Ensure storage directories exist
     if let Err(e) = platform::ensure_dirs() {
@@ -58,18 +147,60 @@ async fn handle_dispatch(command: &str, args: &[String], no_update_check: bool)
         return 1;
     }
 
-    // Load state
+    // Load state. A corrupt file is recovered under the lock rather than
+    // replaced by the next save.
     let mut app_state = match state::AppState::load(&platform::state_file_path()) {
         Ok(s) => s,
-        Err(e) => {
-            eprintln!("{} Failed to load state: {}", "warning:".yellow(), e);
-            state::AppState::default()
+        Err(_) => match lock_and_load_state() {
+            Ok((_, s)) => s,
+            Err(e) => {
+                eprintln!("{} Failed to load state: {}", "warning:".yellow(), e);
+                state::AppState::default()
+            }
+        },
+    };
+
+    // A project lockfile or toolchain file pins the version to run, overriding
+    // the active one. Locked versions take precedence over toolchain ranges.
+    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let (toolchain, lock) = match (
+        toolchain::Toolchain::discover(&cwd),
+        lockfile::Lockfile::discover(&cwd),
+    ) {
+        (Ok(t), Ok(l)) => (t, l),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
         }
     };
+    let locked = lock.as_ref().and_then(|l| l.tool(spec.name));
+    let pinned = toolchain
+        .as_ref()
+        .and_then(|t| t.pin_for(spec.name).map(|pin| (t, pin)));
 
-    // Resolve the binary path: check managed dir, then existing installations, then download
-    let binary_path = if platform::binary_path(spec.name).exists() {
-        platform::binary_path(spec.name)
+    // Resolve the binary path: check lockfile and toolchain pins, managed dir,
+    // then existing installations, then download
+    let (binary_path, version) = if let Some(locked) = locked {
+        match resolve_locked_binary(spec, locked).await {
+            Ok(path) => (path, Some(locked.version.clone())),
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        }
+    } else if let Some((toolchain, pin)) = pinned {
+        match resolve_pinned_binary(spec, pin, &toolchain.path).await {
+            Ok((path, version)) => (path, Some(version)),
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        }
+    } else if platform::binary_path(spec.name).exists() {
+        (
+            platform::binary_path(spec.name),
+            app_state.installed_version(spec.name).cloned(),
+        )
     } else if let Some(existing) = platform::find_existing_binary(spec.name) {
         eprintln!(
             "  {} Found existing {} at {}",
@@ -77,96 +208,111 @@ async fn handle_dispatch(command: &str, args: &[String], no_update_check: bool)
             spec.name,
             existing.display().to_string().dimmed()
         );
-        existing
+        (existing, None)
     } else {
-        // Auto-download if binary is not present anywhere
+        // Auto-download if binary is not present anywhere. Another process may
+        // be installing it already; wait for it instead of downloading twice.
         let managed_path = platform::binary_path(spec.name);
-        eprintln!(
-            "  Retrieving dependencies for {}...",
-            command.bold()
-        );
-
-        let client = match github::build_client() {
-            Ok(c) => c,
-            Err(e) => {
-                eprintln!("{} Failed to create HTTP client: {}", "error:".red(), e);
-                return 1;
+        let _lock = match lock_and_load_state() {
+            Ok((lock, latest)) => {
+                app_state = latest;
+                lock
             }
-        };
-
-        let release = match github::fetch_latest_release(&client, spec).await {
-            Ok(r) => r,
             Err(e) => {
                 eprintln!("{} {}", "error:".red(), e);
                 return 1;
             }
         };
 
-        let asset_name = platform::asset_name(spec.name);
-        let asset = match github::find_asset(&release, &asset_name) {
-            Some(a) => a,
-            None => {
-                eprintln!(
-                    "{} Release asset not found: {}",
-                    "error:".red(),
-                    asset_name
-                );
-                return 1;
-            }
-        };
-
-        let checksum_url = if spec.has_checksum {
-            let checksum_name = platform::checksum_asset_name(spec.name);
-            github::find_asset(&release, &checksum_name)
-                .map(|a| a.browser_download_url.clone())
+        if managed_path.exists() {
+            let version = app_state.installed_version(spec.name).cloned();
+            (managed_path, version)
         } else {
-            None
-        };
+            let started = std::time::Instant::now();
+            eprintln!(
+                "  Retrieving dependencies for {}...",
+                command.bold()
+            );
 
-        if let Err(e) = download::download_and_install(
-            &client,
-            spec,
-            asset,
-            checksum_url.as_deref(),
-            &managed_path,
-        )
-        .await
-        {
-            eprintln!("{} {}", "error:".red(), e);
-            return 1;
-        }
+            let client = match github::build_client() {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("{} Failed to create HTTP client: {}", "error:".red(), e);
+                    return 1;
+                }
+            };
 
-        // Record installation in state
-        let version = github::parse_release_version(&release.tag_name)
-            .unwrap_or_else(|_| semver::Version::new(0, 0, 0));
-        app_state.record_install(spec.name, version, asset_name);
-        let _ = app_state.save(&platform::state_file_path());
+            let release = match github::fetch_latest_release(&client, spec).await {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("{} {}", "error:".red(), e);
+                    return 1;
+                }
+            };
 
-        eprintln!("  {} {} installed successfully", "✓".green(), spec.name);
+            let version = github::parse_release_version(&release.tag_name)
+                .unwrap_or_else(|_| semver::Version::new(0, 0, 0));
 
-        // Hint if ~/.local/bin is not on PATH
-        #[cfg(not(target_os = "windows"))]
-        {
-            let path_var = std::env::var("PATH").unwrap_or_default();
-            if !path_var.split(':').any(|p| p.ends_with(".local/bin")) {
+            let checksum = match update::install_release(
+                &client,
+                spec,
+                &release,
+                &version,
+                &mut app_state,
+                false,
+            )
+            .await
+            {
+                Ok(checksum) => checksum,
+                Err(e) => {
+                    eprintln!("{} {}", "error:".red(), e);
+                    return 1;
+                }
+            };
+            history::record(
+                history::Entry::new(history::Action::Install, spec.name, started)
+                    .versions(None, Some(version.clone()))
+                    .download(platform::asset_name(spec.name), checksum),
+            );
+
+            // Persist the installation recorded by install_release
+            let _ = app_state.save(&platform::state_file_path());
+
+            eprintln!("  {} {} installed successfully", "✓".green(), spec.name);
+
+            // Hint if the managed bin dir is not on PATH
+            if !platform::bin_dir_on_path() {
                 eprintln!(
-                    "  {} add {} to your PATH to run {} directly",
+                    "  {} add {} to your PATH to run {} directly (see `iii-cli doctor`)",
                     "hint:".dimmed(),
-                    "~/.local/bin".bold(),
+                    platform::bin_dir().display().to_string().bold(),
                     spec.name
                 );
             }
-        }
 
-        eprintln!();
+            eprintln!();
 
-        managed_path
+            (managed_path, Some(version))
+        }
     };
 
-    // Run background update check (non-blocking, 500ms timeout)
+    // A forbidden version may already be installed, but must not run
+    if let Some(version) = &version {
+        if let Err(e) = policy::current().check_version(spec, version) {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    }
+
+    // Run background update check (non-blocking, bounded by update.check_timeout_ms)
     if !no_update_check {
-        if let Some((updates, should_save)) =
-            update::run_background_check(&app_state, 500).await
+        let settings = config::current();
+        if let Some((updates, should_save)) = update::run_background_check(
+            &app_state,
+            settings.update_check_interval_hours(),
+            settings.update_check_timeout(),
+        )
+        .await
         {
             // Print update notifications
             update::print_update_notifications(&updates);
@@ -179,14 +325,27 @@ async fn handle_dispatch(command: &str, args: &[String], no_update_check: bool)
                 }
             }
 
-            // Save updated state
+            // Save updated state, unless another process holds the lock (the
+            // next run checks again). Reload first so its changes are kept.
             if should_save {
-                app_state.mark_update_checked();
-                let _ = app_state.save(&platform::state_file_path());
+                if let Ok(Some(_lock)) = lock::try_lock_data_dir() {
+                    let path = platform::state_file_path();
+                    if let Ok(mut latest) = state::AppState::load(&path) {
+                        latest.mark_update_checked();
+                        let _ = latest.save(&path);
+                    }
+                }
             }
         }
     }
 
+    // Something else on the machine may have replaced the managed binary
+    // since iii-cli installed it
+    let recorded = recorded_file(spec, &binary_path, version.as_ref(), &app_state);
+    if !verify::allow_run(&binary_path, recorded.as_ref(), config::current().tamper_check()) {
+        return 1;
+    }
+
     // Build args for the child binary
     let mut child_args: Vec<String> = Vec::new();
     if let Some(subcmd) = binary_subcommand {
@@ -204,8 +363,160 @@ async fn handle_dispatch(command: &str, args: &[String], no_update_check: bool)
     }
 }
 
-/// Handle the update command.
-async fn handle_update(target: Option<&str>) -> i32 {
+/// Lock the data directory and load state, recovering a corrupt state file.
+fn lock_and_load_state() -> Result<(lock::DataLock, state::AppState), error::IiiCliError> {
+    let lock = lock::lock_data_dir()?;
+    let state = state::AppState::load_or_recover(&platform::state_file_path(), &lock)?;
+    state.check_writable()?;
+    Ok((lock, state))
+}
+
+/// The fingerprint recorded when a managed binary was installed: in state
+/// for the bin_dir() copy, in the versioned store for a stored version.
+fn recorded_file(
+    spec: &registry::BinarySpec,
+    path: &std::path::Path,
+    version: Option<&semver::Version>,
+    app_state: &state::AppState,
+) -> Option<ownership::FileRecord> {
+    if path == platform::binary_path(spec.name) {
+        let entry = app_state.binaries.get(spec.name)?;
+        return entry.file.clone().or_else(|| {
+            entry
+                .hashes
+                .as_ref()
+                .map(|h| ownership::FileRecord::hash_only(h.binary_sha256.clone()))
+        });
+    }
+    let version = version?;
+    if path != platform::versioned_binary_path(spec.name, version) {
+        return None;
+    }
+    store::recorded_file(spec.name, version)
+}
+
+/// Resolve the versioned binary satisfying a toolchain pin, installing the
+/// best matching release into the versioned store first if needed.
+async fn resolve_pinned_binary(
+    spec: &'static registry::BinarySpec,
+    pin: &toolchain::VersionPin,
+    toolchain_path: &std::path::Path,
+) -> Result<(std::path::PathBuf, semver::Version), update::UpdateError> {
+    let policy = policy::current();
+    let allowed = |v: &semver::Version| pin.matches(v) && !policy.is_forbidden(spec.name, v);
+    if let Some(version) = store::best_stored_match(spec.name, allowed) {
+        return Ok((platform::versioned_binary_path(spec.name, &version), version));
+    }
+
+    eprintln!(
+        "  Retrieving {} {} pinned by {}...",
+        spec.name,
+        pin.to_string().bold(),
+        toolchain_path.display().to_string().dimmed()
+    );
+    // Another process may install a match while we wait for the lock;
+    // ensure_pinned_version looks in the store again once it is held
+    let _lock = lock::lock_data_dir()?;
+    let client = github::build_client().map_err(github::IiiGithubError::from)?;
+    let version = update::ensure_pinned_version(&client, spec, pin).await?;
+    eprintln!("  {} {} v{} installed successfully", "✓".green(), spec.name, version);
+    eprintln!();
+    Ok((platform::versioned_binary_path(spec.name, &version), version))
+}
+
+/// Resolve the versioned binary recorded in a lockfile, installing the locked
+/// archive into the versioned store first if needed.
+async fn resolve_locked_binary(
+    spec: &'static registry::BinarySpec,
+    locked: &lockfile::LockedTool,
+) -> Result<std::path::PathBuf, update::UpdateError> {
+    if update::is_locked_installed(spec, locked) {
+        return Ok(platform::versioned_binary_path(spec.name, &locked.version));
+    }
+
+    // Another process may install it while we wait for the lock
+    let _lock = lock::lock_data_dir()?;
+    if !update::is_locked_installed(spec, locked) {
+        eprintln!(
+            "  Retrieving {} v{} locked by {}...",
+            spec.name,
+            locked.version.to_string().bold(),
+            lockfile::LOCKFILE_NAME.dimmed()
+        );
+        let client = github::build_client().map_err(github::IiiGithubError::from)?;
+        update::install_locked(&client, spec, locked).await?;
+        eprintln!("  {} {} v{} installed successfully", "✓".green(), spec.name, locked.version);
+        eprintln!();
+    }
+    Ok(platform::versioned_binary_path(spec.name, &locked.version))
+}
+
+/// Handle the uninstall command.
+fn handle_uninstall(target: &str, force: bool) -> i32 {
+    if matches!(target, "iii-cli" | "self") {
+        eprintln!(
+            "{} iii-cli cannot uninstall itself. Remove {} manually.",
+            "error:".red(),
+            platform::binary_path("iii-cli").display()
+        );
+        return 1;
+    }
+
+    let spec = match registry::resolve_update_target(target) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let (_lock, mut app_state) = match lock_and_load_state() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let result = match update::uninstall_binary(spec, &mut app_state, force) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    if let Err(e) = app_state.save(&platform::state_file_path()) {
+        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
+    }
+
+    match &result.active {
+        Some(version) => eprintln!(
+            "  {} {} v{} uninstalled",
+            "✓".green(),
+            result.binary,
+            version
+        ),
+        None => eprintln!("  {} {} uninstalled", "✓".green(), result.binary),
+    }
+    if !result.removed_versions.is_empty() {
+        let versions: Vec<String> = result.removed_versions.iter().map(|v| v.to_string()).collect();
+        eprintln!("      removed stored versions: {}", versions.join(", ").dimmed());
+    }
+    0
+}
+
+/// Handle the lock command: resolve every tool and write the project lockfile.
+async fn handle_lock() -> i32 {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let toolchain = match toolchain::Toolchain::discover(&cwd) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
     let client = match github::build_client() {
         Ok(c) => c,
         Err(e) => {
@@ -214,88 +525,393 @@ async fn handle_update(target: Option<&str>) -> i32 {
         }
     };
 
-    let mut app_state = match state::AppState::load(&platform::state_file_path()) {
-        Ok(s) => s,
+    let lock = match lockfile::generate(&client, toolchain.as_ref()).await {
+        Ok(l) => l,
         Err(e) => {
-            eprintln!("{} Failed to load state: {}", "error:".red(), e);
+            eprintln!("{} {}", "error:".red(), e);
             return 1;
         }
     };
 
-    // Ensure storage directories exist
+    let path = lockfile::lockfile_path(&cwd, toolchain.as_ref());
+    if let Err(e) = lock.save(&path) {
+        eprintln!("{} {}", "error:".red(), e);
+        return 1;
+    }
+
+    eprintln!(
+        "  {} Locked {} tools in {}",
+        "✓".green(),
+        lock.tools.len(),
+        path.display().to_string().dimmed()
+    );
+    0
+}
+
+/// Handle the sync command: install the project's pinned or locked tools
+/// into the versioned store.
+async fn handle_sync(locked: bool) -> i32 {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+
     if let Err(e) = platform::ensure_dirs() {
         eprintln!("{} {}", "error:".red(), e);
         return 1;
     }
 
-    let results = match target {
-        Some("iii-cli" | "self") => {
-            // Self-update only
-            vec![update::self_update(&client, &mut app_state).await]
+    let client = match github::build_client() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} Failed to create HTTP client: {}", "error:".red(), e);
+            return 1;
         }
-        Some(cmd) => {
-            // Normalize SDK-namespaced commands to registry keys
-            let registry_key = match cmd {
-                "sdk" => "motia-cli",
-                other => other,
-            };
-            // Update specific binary
-            let spec = match registry::resolve_binary_for_update(registry_key) {
+    };
+
+    let _lock = match lock::lock_data_dir() {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let mut failed = false;
+
+    if locked {
+        let lock = match lockfile::Lockfile::discover(&cwd) {
+            Ok(Some(l)) => l,
+            Ok(None) => {
+                eprintln!(
+                    "{} {}",
+                    "error:".red(),
+                    error::ToolchainError::NotFound {
+                        file: lockfile::LOCKFILE_NAME.to_string(),
+                        dir: cwd.display().to_string(),
+                        hint: "Run 'iii-cli lock' first.".to_string(),
+                    }
+                );
+                return 1;
+            }
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        };
+
+        for tool in &lock.tools {
+            let spec = match registry::resolve_binary_for_update(&tool.name) {
                 Ok(s) => s,
                 Err(e) => {
-                    eprintln!("{} {}", "error:".red(), e);
-                    return 1;
+                    eprintln!("  {} {}", "error:".red(), e);
+                    failed = true;
+                    continue;
                 }
             };
-            vec![update::update_binary(&client, spec, &mut app_state).await]
-        }
-        None => {
-            // Update all (includes self-update)
-            eprintln!("  Checking all binaries for updates...");
-            update::update_all(&client, &mut app_state).await
+            if tool.artifact_for(platform::current_target()).is_none() {
+                eprintln!(
+                    "  {} {} v{} is not locked for {}, skipping",
+                    "warning:".yellow(),
+                    tool.name,
+                    tool.version,
+                    platform::current_target()
+                );
+                continue;
+            }
+            let result = update::install_locked(&client, spec, tool).await;
+            failed |= result.is_err();
+            update::print_update_result(&result);
         }
-    };
+    } else {
+        let toolchain = match toolchain::Toolchain::discover(&cwd) {
+            Ok(Some(t)) => t,
+            Ok(None) => {
+                eprintln!(
+                    "{} {}",
+                    "error:".red(),
+                    error::ToolchainError::NotFound {
+                        file: toolchain::TOOLCHAIN_FILE.to_string(),
+                        dir: cwd.display().to_string(),
+                        hint: "Add one to pin tool versions for this project.".to_string(),
+                    }
+                );
+                return 1;
+            }
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        };
 
-    // Print results
-    let mut self_updated = false;
-    for result in &results {
-        update::print_update_result(result);
-        if let Ok(update::UpdateResult::Updated { binary, .. }) = result {
-            if binary == "iii-cli" {
-                self_updated = true;
+        for (name, pin) in &toolchain.pins {
+            let spec = match registry::resolve_binary_for_update(name) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("  {} {}", "error:".red(), e);
+                    failed = true;
+                    continue;
+                }
+            };
+            match update::ensure_pinned_version(&client, spec, pin).await {
+                Ok(version) => eprintln!(
+                    "  {} {} v{} (pinned {})",
+                    "✓".green(),
+                    name,
+                    version.to_string().green(),
+                    pin
+                ),
+                Err(e) => {
+                    eprintln!("  {} {}", "error:".red(), e);
+                    failed = true;
+                }
             }
         }
     }
 
-    // Print restart note after self-update
-    if self_updated {
-        eprintln!();
-        eprintln!(
-            "  {} iii-cli has been updated. Restart your shell or run the command again to use the new version.",
-            "note:".cyan(),
-        );
-    }
-
-    // Save state
-    app_state.mark_update_checked();
-    if let Err(e) = app_state.save(&platform::state_file_path()) {
-        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
-    }
-
-    // Return non-zero if any update failed
-    if results.iter().any(|r| r.is_err()) {
+    if failed {
         1
     } else {
         0
     }
 }
 
-/// Handle the list command.
-fn handle_list() -> i32 {
-    let app_state = match state::AppState::load(&platform::state_file_path()) {
-        Ok(s) => s,
+/// Handle the install command.
+async fn handle_install(target: &str, force: bool) -> i32 {
+    let (name, version) = match registry::parse_versioned_target(target) {
+        Ok(parsed) => parsed,
         Err(e) => {
-            eprintln!("{} Failed to load state: {}", "error:".red(), e);
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let spec = match registry::resolve_update_target(name) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let client = match github::build_client() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} Failed to create HTTP client: {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let (_lock, mut app_state) = match lock_and_load_state() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    if let Err(e) = platform::ensure_dirs() {
+        eprintln!("{} {}", "error:".red(), e);
+        return 1;
+    }
+
+    let result = match version {
+        Some(version) => {
+            update::install_version(&client, spec, &version, &mut app_state, force).await
+        }
+        None => update::update_binary(&client, spec, &mut app_state, force).await,
+    };
+    update::print_update_result(&result);
+
+    if let Err(e) = app_state.save(&platform::state_file_path()) {
+        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
+    }
+
+    if result.is_err() {
+        1
+    } else {
+        0
+    }
+}
+
+/// Handle the use command: switch the active version of a binary to one
+/// already held in the versioned store.
+fn handle_use(target: &str, force: bool) -> i32 {
+    let (name, version) = match registry::parse_versioned_target(target) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let spec = match registry::resolve_update_target(name) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let version = match version {
+        Some(v) => v,
+        None => {
+            let stored = store::stored_versions(spec.name);
+            eprintln!(
+                "{} No version given. Use {}@<version>.",
+                "error:".red(),
+                name
+            );
+            if stored.is_empty() {
+                eprintln!("  No versions of {} are installed.", spec.name);
+            } else {
+                let stored: Vec<String> = stored.iter().map(|v| v.to_string()).collect();
+                eprintln!("  Installed versions of {}: {}", spec.name, stored.join(", "));
+            }
+            return 1;
+        }
+    };
+
+    let (_lock, mut app_state) = match lock_and_load_state() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let started = std::time::Instant::now();
+    let previous = app_state.installed_version(spec.name).cloned();
+    if let Err(e) = update::activate_version(spec, &version, &mut app_state, force) {
+        eprintln!("{} {}", "error:".red(), e);
+        return 1;
+    }
+    history::record(
+        history::Entry::new(history::Action::Switch, spec.name, started)
+            .versions(previous, Some(version.clone())),
+    );
+
+    if let Err(e) = app_state.save(&platform::state_file_path()) {
+        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
+    }
+
+    eprintln!(
+        "  {} {} is now using v{}",
+        "✓".green(),
+        spec.name,
+        version.to_string().green()
+    );
+    0
+}
+
+/// Handle the update command.
+async fn handle_update(target: Option<&str>, version: Option<&str>, force: bool) -> i32 {
+    let client = match github::build_client() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} Failed to create HTTP client: {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    // Refresh the registry manifest; new tools become available on the next run
+    if let Err(e) = remote_registry::refresh(&client).await {
+        eprintln!("  {} {}", "warning:".yellow(), e);
+    }
+
+    let (_lock, mut app_state) = match lock_and_load_state() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    // Ensure storage directories exist
+    if let Err(e) = platform::ensure_dirs() {
+        eprintln!("{} {}", "error:".red(), e);
+        return 1;
+    }
+
+    let version = match version.map(registry::parse_version).transpose() {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let results = match (target, version) {
+        (Some(cmd), Some(version)) => {
+            // Install an exact version of a specific binary
+            let spec = match registry::resolve_update_target(cmd) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("{} {}", "error:".red(), e);
+                    return 1;
+                }
+            };
+            vec![update::install_version(&client, spec, &version, &mut app_state, force).await]
+        }
+        (Some("iii-cli" | "self"), None) => {
+            // Self-update only
+            vec![update::self_update(&client, &mut app_state).await]
+        }
+        (Some(cmd), None) => {
+            // Update specific binary
+            let spec = match registry::resolve_update_target(cmd) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("{} {}", "error:".red(), e);
+                    return 1;
+                }
+            };
+            vec![update::update_binary(&client, spec, &mut app_state, force).await]
+        }
+        (None, _) => {
+            // Update all (includes self-update)
+            eprintln!("  Checking all binaries for updates...");
+            update::update_all(&client, &mut app_state, force).await
+        }
+    };
+
+    // Print results
+    let mut self_updated = false;
+    for result in &results {
+        update::print_update_result(result);
+        if let Ok(update::UpdateResult::Updated { binary, .. }) = result {
+            if binary == "iii-cli" {
+                self_updated = true;
+            }
+        }
+    }
+
+    // Print restart note after self-update
+    if self_updated {
+        eprintln!();
+        eprintln!(
+            "  {} iii-cli has been updated. Restart your shell or run the command again to use the new version.",
+            "note:".cyan(),
+        );
+    }
+
+    // Save state
+    app_state.mark_update_checked();
+    if let Err(e) = app_state.save(&platform::state_file_path()) {
+        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
+    }
+
+    // Return non-zero if any update failed
+    if results.iter().any(|r| r.is_err()) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Handle the list command.
+fn handle_list(plugins: &[plugin::Plugin]) -> i32 {
+    let app_state = match state::AppState::load(&platform::state_file_path()) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} Failed to load state: {}", "error:".red(), e);
             return 1;
         }
     };
@@ -303,6 +919,7 @@ fn handle_list() -> i32 {
     if app_state.binaries.is_empty() {
         eprintln!("  No binaries installed yet. Run a command to auto-install its dependency.");
         eprintln!("  Available commands: {}", registry::available_commands().join(", "));
+        print_plugins(plugins);
         return 0;
     }
 
@@ -310,18 +927,7 @@ fn handle_list() -> i32 {
     eprintln!();
 
     for (name, binary_state) in &app_state.binaries {
-        let cmd = registry::REGISTRY
-            .iter()
-            .find(|s| s.name == name)
-            .and_then(|s| s.commands.first())
-            .map(|c| {
-                if c.cli_command == "motia" {
-                    "sdk motia"
-                } else {
-                    c.cli_command
-                }
-            })
-            .unwrap_or("?");
+        let cmd = registry::command_path_for_binary(name).unwrap_or_else(|| "?".to_string());
 
         eprintln!(
             "  {} {} (v{}) — installed {} — command: iii-cli {}",
@@ -331,6 +937,19 @@ fn handle_list() -> i32 {
             binary_state.installed_at.format("%Y-%m-%d"),
             cmd,
         );
+
+        let others: Vec<String> = store::stored_versions(name)
+            .iter()
+            .filter(|v| **v != binary_state.version)
+            .map(|v| v.to_string())
+            .collect();
+        if !others.is_empty() {
+            eprintln!(
+                "      also installed: {} (switch with `iii-cli use {}@<version>`)",
+                others.join(", ").dimmed(),
+                name,
+            );
+        }
     }
 
     eprintln!();
@@ -338,5 +957,343 @@ fn handle_list() -> i32 {
         "  Storage: {}",
         platform::bin_dir().display().to_string().dimmed()
     );
+    if let Some(path) = policy::current().path() {
+        eprintln!("  Policy: {}", path.display().to_string().dimmed());
+    }
+    print_plugins(plugins);
+    0
+}
+
+fn print_plugins(plugins: &[plugin::Plugin]) {
+    if plugins.is_empty() {
+        return;
+    }
+
+    eprintln!();
+    eprintln!("  Plugins:");
+    eprintln!();
+    for plugin in plugins {
+        eprintln!(
+            "  {} {} — {}",
+            "•".dimmed(),
+            plugin.name.bold(),
+            plugin.path.display().to_string().dimmed(),
+        );
+    }
+}
+
+/// Handle the rollback command: restore the version a binary had before
+/// its last update or switch.
+fn handle_rollback(target: &str, force: bool) -> i32 {
+    let spec = match registry::resolve_update_target(target) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let (_lock, mut app_state) = match lock_and_load_state() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let (from, to) = match update::rollback_binary(spec, &mut app_state, force) {
+        Ok(versions) => versions,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    if let Err(e) = app_state.save(&platform::state_file_path()) {
+        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
+    }
+
+    eprintln!(
+        "  {} {} rolled back: {} → {}",
+        "✓".green(),
+        spec.name,
+        from.to_string().dimmed(),
+        to.to_string().green()
+    );
+    0
+}
+
+/// Handle the history command.
+fn handle_history(target: Option<&str>) -> i32 {
+    let binary = match target.map(registry::resolve_update_target).transpose() {
+        Ok(spec) => spec.map(|s| s.name),
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    let path = platform::history_file_path();
+    let entries = match history::read(&path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("{} Failed to read {}: {}", "error:".red(), path.display(), e);
+            return 1;
+        }
+    };
+    let entries: Vec<history::Entry> = entries
+        .into_iter()
+        .filter(|e| binary.is_none_or(|name| e.binary == name))
+        .collect();
+
+    if entries.is_empty() {
+        match binary {
+            Some(name) => eprintln!("  No install history for {}.", name),
+            None => eprintln!("  No install history yet."),
+        }
+        return 0;
+    }
+    history::print_entries(&entries);
+    0
+}
+
+/// Handle the verify command: re-hash installed binaries, optionally
+/// downloading damaged ones again.
+async fn handle_verify(target: Option<&str>, repair: bool) -> i32 {
+    // Repairs write binaries and state; a plain check only reads them
+    let (_lock, mut app_state) = if repair {
+        match lock_and_load_state() {
+            Ok((lock, state)) => (Some(lock), state),
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        }
+    } else {
+        match state::AppState::load(&platform::state_file_path()) {
+            Ok(s) => (None, s),
+            Err(e) => {
+                eprintln!("{} Failed to load state: {}", "error:".red(), e);
+                return 1;
+            }
+        }
+    };
+
+    let specs: Vec<&'static registry::BinarySpec> = match target {
+        Some(target) => match registry::resolve_update_target(target) {
+            Ok(spec) => vec![spec],
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        },
+        None => std::iter::once(&registry::SELF_SPEC)
+            .chain(registry::all_binaries())
+            .filter(|spec| {
+                app_state.binaries.contains_key(spec.name)
+                    || !store::stored_versions(spec.name).is_empty()
+            })
+            .collect(),
+    };
+
+    let checks: Vec<verify::FileCheck> = specs
+        .iter()
+        .flat_map(|spec| verify::verify_binary(spec.name, &app_state))
+        .collect();
+    if checks.is_empty() {
+        eprintln!("  No managed binaries to verify.");
+        return 0;
+    }
+    verify::print_checks(&checks);
+
+    let mut damaged: Vec<(&'static registry::BinarySpec, semver::Version)> = Vec::new();
+    for check in checks.iter().filter(|c| c.integrity.is_damaged()) {
+        let spec = specs.iter().find(|s| s.name == check.binary).copied();
+        if let Some(spec) = spec {
+            if !damaged.iter().any(|(s, v)| s.name == spec.name && *v == check.version) {
+                damaged.push((spec, check.version.clone()));
+            }
+        }
+    }
+
+    if damaged.is_empty() {
+        return 0;
+    }
+    if !repair {
+        eprintln!();
+        eprintln!(
+            "  {} run `iii-cli verify --repair` to download damaged binaries again",
+            "hint:".dimmed()
+        );
+        return 1;
+    }
+
+    let client = match github::build_client() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{} Failed to create HTTP client: {}", "error:".red(), e);
+            return 1;
+        }
+    };
+
+    eprintln!();
+    let mut exit_code = 0;
+    for (spec, version) in damaged {
+        eprintln!("  Repairing {} v{}...", spec.name, version);
+        match update::repair_version(&client, spec, &version, &mut app_state).await {
+            Ok(()) => eprintln!("  {} {} v{} repaired", "✓".green(), spec.name, version),
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                exit_code = 1;
+            }
+        }
+    }
+
+    if let Err(e) = app_state.save(&platform::state_file_path()) {
+        eprintln!("{} Failed to save state: {}", "warning:".yellow(), e);
+    }
+    exit_code
+}
+
+/// Handle the doctor command. Exits non-zero if any check failed.
+async fn handle_doctor(json: bool) -> i32 {
+    let (app_state, state_finding) = match state::AppState::load(&platform::state_file_path()) {
+        Ok(s) => (s, None),
+        Err(e) => (state::AppState::default(), Some(e)),
+    };
+    let client = github::build_client().ok();
+
+    let mut findings = doctor::run(client.as_ref(), &app_state).await;
+    if let Some(e) = state_finding {
+        findings.push(doctor::Finding {
+            check: "state",
+            status: doctor::Status::Error,
+            message: format!("Failed to load state: {}", e),
+            hint: Some(
+                "run `iii-cli verify --repair` to back up the file and recover what still parses"
+                    .to_string(),
+            ),
+        });
+    }
+
+    if json {
+        match serde_json::to_string_pretty(&findings) {
+            Ok(out) => println!("{}", out),
+            Err(e) => {
+                eprintln!("{} {}", "error:".red(), e);
+                return 1;
+            }
+        }
+    } else {
+        doctor::print_findings(&findings);
+    }
+
+    if findings.iter().any(|f| f.status == doctor::Status::Error) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Print the effective value of one config key.
+fn handle_config_get(key: &str, show_origin: bool) -> i32 {
+    let Some(entry) = config::current().get(key) else {
+        // Like `git config`, an unset key exits non-zero without output
+        return 1;
+    };
+    if show_origin {
+        println!("{}\t{}", entry.origin, entry.display_value());
+    } else {
+        println!("{}", entry.display_value());
+    }
+    0
+}
+
+/// Write a config key to the system, user or project config file.
+fn handle_config_set(key: &str, value: &str, scope: config::Scope) -> i32 {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let path = config::scope_path(scope, &cwd);
+    let result = config::check_scope(scope, key, &path).and_then(|()| config::set(&path, key, value));
+    if let Err(e) = result {
+        eprintln!("{} {}", "error:".red(), e);
+        return 1;
+    }
+    eprintln!("  {} Set {} in {}", "✓".green(), key.bold(), path.display());
+    0
+}
+
+/// Print every effective config value, masking secrets.
+fn handle_config_list(show_origin: bool) -> i32 {
+    for (key, entry) in config::current().entries() {
+        let value = if config::Config::is_secret(key) {
+            "********".to_string()
+        } else {
+            entry.display_value()
+        };
+        if show_origin {
+            println!("{}\t{} = {}", entry.origin, key, value);
+        } else {
+            println!("{} = {}", key, value);
+        }
+    }
+    0
+}
+
+/// Handle the cache list command.
+fn handle_cache_list() -> i32 {
+    let cache = cache::Cache::open_default();
+    let entries = cache.entries();
+    if entries.is_empty() {
+        eprintln!("  The download cache is empty.");
+        return 0;
+    }
+    cache::print_entries(&entries);
+    0
+}
+
+/// Handle the cache clean command.
+fn handle_cache_clean() -> i32 {
+    // An install may be reading or adding archives
+    let _lock = match lock::lock_data_dir() {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            return 1;
+        }
+    };
+    match cache::Cache::open_default().clear() {
+        Ok(freed) => {
+            eprintln!(
+                "  {} Removed cached archives ({} freed)",
+                "✓".green(),
+                cache::format_size(freed)
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!(
+                "{} Failed to clear {}: {}",
+                "error:".red(),
+                platform::cache_dir().display(),
+                e
+            );
+            1
+        }
+    }
+}
+
+/// Handle the cache size command.
+fn handle_cache_size() -> i32 {
+    let cache = cache::Cache::open_default();
+    let limit = match config::current().cache_max_size() {
+        0 => "caching disabled".to_string(),
+        limit => format!("limit {}", cache::format_size(limit)),
+    };
+    println!(
+        "{} in {} archive(s) ({})",
+        cache::format_size(cache.size()),
+        cache.entries().len(),
+        limit
+    );
     0
 }
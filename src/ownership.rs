@@ -0,0 +1,293 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::StorageError;
+use crate::state::BinaryState;
+
+/// Fingerprint of a file iii-cli wrote, used to tell its own files apart
+/// from foreign or locally modified ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileRecord {
+    /// SHA256 of the file contents, lowercase hex
+    pub sha256: String,
+
+    /// File size in bytes
+    pub size: u64,
+
+    /// Last modification time when the file was written
+    pub modified: Option<DateTime<Utc>>,
+
+    /// Inode number (Unix only)
+    #[serde(default)]
+    pub inode: Option<u64>,
+}
+
+impl FileRecord {
+    /// A record holding only a hash, for files recorded before they were
+    /// fingerprinted. Its metadata never matches, so the file is re-hashed.
+    pub fn hash_only(sha256: String) -> Self {
+        Self {
+            sha256,
+            size: 0,
+            modified: None,
+            inode: None,
+        }
+    }
+
+    /// Fingerprint the file currently at `path`.
+    pub fn capture(path: &Path) -> std::io::Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Self {
+            sha256: hash_file(path)?,
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            inode: inode(&metadata),
+        })
+    }
+
+    /// Cheap check: do size, mtime and inode still match the recorded values?
+    pub fn metadata_matches(&self, metadata: &std::fs::Metadata) -> bool {
+        self.size == metadata.len()
+            && self.modified == metadata.modified().ok().map(DateTime::<Utc>::from)
+            && self.inode == inode(metadata)
+    }
+}
+
+/// Who a file at a managed path belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ownership {
+    /// Nothing at the path
+    Missing,
+    /// Written by iii-cli and unchanged since
+    Owned,
+    /// Recorded in state by an iii-cli version that did not fingerprint files
+    Untracked,
+    /// Written by iii-cli but changed since
+    Modified,
+    /// Not written by iii-cli
+    Foreign,
+    /// Found in bin_dir() when state was rebuilt; may not be iii-cli's
+    Unverified,
+}
+
+impl Ownership {
+    /// Whether iii-cli may replace or delete the file without asking.
+    pub fn is_safe_to_replace(self) -> bool {
+        matches!(self, Self::Missing | Self::Owned | Self::Untracked)
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Modified => "was modified after iii-cli installed it",
+            Self::Foreign => "was not installed by iii-cli",
+            Self::Unverified => "was found when state was rebuilt and may not have been installed by iii-cli",
+            Self::Missing | Self::Owned | Self::Untracked => "is managed by iii-cli",
+        }
+    }
+}
+
+/// Determine who owns the file at `path`, given the binary's state entry.
+///
+/// Size, mtime and inode are compared first; the file is only re-hashed when
+/// they differ, so files that were merely touched or copied still count as owned.
+pub fn check(path: &Path, entry: Option<&BinaryState>) -> Ownership {
+    let metadata = match std::fs::metadata(path) {
+        Ok(m) => m,
+        Err(_) => return Ownership::Missing,
+    };
+
+    let record = match entry {
+        None => return Ownership::Foreign,
+        Some(BinaryState {
+            recovered: true, ..
+        }) => return Ownership::Unverified,
+        Some(BinaryState { file: None, .. }) => return Ownership::Untracked,
+        Some(BinaryState {
+            file: Some(record), ..
+        }) => record,
+    };
+
+    if record.metadata_matches(&metadata) {
+        return Ownership::Owned;
+    }
+
+    match hash_file(path) {
+        Ok(hash) if hash == record.sha256 => Ownership::Owned,
+        _ => Ownership::Modified,
+    }
+}
+
+/// Make sure iii-cli may `action` ("replace", "remove") the file at `path`.
+///
+/// Files iii-cli owns pass silently. Otherwise `--force` allows the action,
+/// an interactive terminal is asked for confirmation, and anything else fails.
+pub fn ensure_may_modify(
+    path: &Path,
+    entry: Option<&BinaryState>,
+    action: &str,
+    force: bool,
+) -> Result<(), StorageError> {
+    let ownership = check(path, entry);
+    if ownership.is_safe_to_replace() {
+        return Ok(());
+    }
+
+    if force {
+        eprintln!(
+            "  {} {} {}; {} it because of --force",
+            "warning:".yellow(),
+            path.display(),
+            ownership.describe(),
+            action_progressive(action),
+        );
+        return Ok(());
+    }
+
+    if std::io::stdin().is_terminal()
+        && confirm(&format!(
+            "  {} {} {}. {} it? [y/N] ",
+            "warning:".yellow(),
+            path.display(),
+            ownership.describe(),
+            capitalize(action),
+        ))
+    {
+        return Ok(());
+    }
+
+    Err(StorageError::NotOwned {
+        path: path.display().to_string(),
+        reason: ownership.describe().to_string(),
+        action: action.to_string(),
+    })
+}
+
+/// Ask a yes/no question on stderr and read the answer from stdin.
+fn confirm(prompt: &str) -> bool {
+    eprint!("{}", prompt);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn action_progressive(action: &str) -> String {
+    match action.strip_suffix('e') {
+        Some(stem) => format!("{}ing", stem),
+        None => format!("{}ing", action),
+    }
+}
+
+/// Compute the SHA256 of a file without loading it into memory at once.
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use semver::Version;
+
+    fn entry(file: Option<FileRecord>) -> BinaryState {
+        BinaryState {
+            version: Version::new(0, 4, 2),
+            installed_at: Utc::now(),
+            asset_name: "iii.tar.gz".to_string(),
+            file,
+            hashes: None,
+            recovered: false,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_check_owned_and_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"engine").unwrap();
+
+        let state = entry(Some(FileRecord::capture(&path).unwrap()));
+        assert_eq!(check(&path, Some(&state)), Ownership::Owned);
+        assert_eq!(check(&dir.path().join("missing"), Some(&state)), Ownership::Missing);
+    }
+
+    #[test]
+    fn test_check_foreign_and_untracked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"hand-built").unwrap();
+
+        assert_eq!(check(&path, None), Ownership::Foreign);
+        assert_eq!(check(&path, Some(&entry(None))), Ownership::Untracked);
+        assert!(!Ownership::Foreign.is_safe_to_replace());
+        assert!(Ownership::Untracked.is_safe_to_replace());
+    }
+
+    #[test]
+    fn test_check_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"engine").unwrap();
+        let state = entry(Some(FileRecord::capture(&path).unwrap()));
+
+        std::fs::write(&path, b"replaced by something else").unwrap();
+        assert_eq!(check(&path, Some(&state)), Ownership::Modified);
+    }
+
+    #[test]
+    fn test_check_same_content_new_metadata_is_owned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"engine").unwrap();
+        let mut record = FileRecord::capture(&path).unwrap();
+        // Simulate a copy: same bytes, different inode and mtime
+        record.inode = record.inode.map(|i| i + 1);
+        record.modified = None;
+
+        assert_eq!(check(&path, Some(&entry(Some(record)))), Ownership::Owned);
+    }
+
+    #[test]
+    fn test_ensure_may_modify_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"hand-built").unwrap();
+
+        assert!(ensure_may_modify(&path, None, "replace", true).is_ok());
+    }
+
+    #[test]
+    fn test_action_progressive() {
+        assert_eq!(action_progressive("replace"), "replacing");
+        assert_eq!(action_progressive("remove"), "removing");
+    }
+}
@@ -29,7 +29,12 @@ pub fn current_target() -> &'static str {
 
 /// Returns the archive extension for the current platform.
 pub fn archive_extension() -> &'static str {
-    if cfg!(target_os = "windows") {
+    archive_extension_for_target(current_target())
+}
+
+/// Returns the archive extension used by release assets for a target triple.
+pub fn archive_extension_for_target(target: &str) -> &'static str {
+    if target.contains("-windows-") {
         "zip"
     } else {
         "tar.gz"
@@ -42,6 +47,16 @@ pub fn asset_name(binary_name: &str) -> String {
     format!("{}-{}.{}", binary_name, current_target(), archive_extension())
 }
 
+/// Constructs the expected asset filename for a binary on any target triple.
+pub fn asset_name_for_target(binary_name: &str, target: &str) -> String {
+    format!(
+        "{}-{}.{}",
+        binary_name,
+        target,
+        archive_extension_for_target(target)
+    )
+}
+
 /// Returns the platform-appropriate data directory for iii-cli.
 ///
 /// - Linux: $XDG_DATA_HOME/iii-cli/ (fallback ~/.local/share/iii-cli/)
@@ -89,11 +104,98 @@ pub fn binary_path(binary_name: &str) -> PathBuf {
     bin_dir().join(name)
 }
 
+/// Returns the directory holding side-by-side versions of managed binaries.
+///
+/// Layout: `<data_dir>/versions/<binary>/<version>/<binary>`
+pub fn versions_dir() -> PathBuf {
+    data_dir().join("versions")
+}
+
+/// Returns the directory archives are downloaded into before extraction.
+pub fn downloads_dir() -> PathBuf {
+    data_dir().join("downloads")
+}
+
+/// Returns the directory of the content-addressed download cache.
+pub fn cache_dir() -> PathBuf {
+    data_dir().join("cache")
+}
+
+/// Returns the path where a specific version of a binary is stored.
+pub fn versioned_binary_path(binary_name: &str, version: &semver::Version) -> PathBuf {
+    versions_dir()
+        .join(binary_name)
+        .join(version.to_string())
+        .join(exe_name(binary_name))
+}
+
+/// Returns the on-disk file name of a binary (adds ".exe" on Windows).
+pub fn exe_name(binary_name: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("{}.exe", binary_name)
+    } else {
+        binary_name.to_string()
+    }
+}
+
+/// Returns the platform-appropriate config directory for iii-cli.
+///
+/// - Linux: $XDG_CONFIG_HOME/iii-cli/ (fallback ~/.config/iii-cli/)
+/// - macOS: ~/Library/Application Support/iii-cli/
+/// - Windows: %APPDATA%\iii-cli\
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".config")
+        })
+        .join("iii-cli")
+}
+
+/// Returns the path to the user config.toml file.
+pub fn user_config_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+/// Returns the machine-wide config directory, managed by administrators.
+///
+/// - macOS/Linux: /etc/iii-cli/
+/// - Windows: %PROGRAMDATA%\iii-cli\
+pub fn system_config_dir() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var_os("PROGRAMDATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"))
+            .join("iii-cli")
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        PathBuf::from("/etc/iii-cli")
+    }
+}
+
+/// Returns the path to the system config.toml file.
+pub fn system_config_path() -> PathBuf {
+    system_config_dir().join("config.toml")
+}
+
+/// Returns the path to the administrator policy file.
+pub fn policy_path() -> PathBuf {
+    system_config_dir().join("policy.toml")
+}
+
 /// Returns the path to the state.json file.
 pub fn state_file_path() -> PathBuf {
     data_dir().join("state.json")
 }
 
+/// Returns the path to the install history log.
+pub fn history_file_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
 /// Checks whether the current platform is supported by the given binary.
 /// Returns Ok(()) if supported, or an error with a helpful message if not.
 pub fn check_platform_support(spec: &BinarySpec) -> Result<(), RegistryError> {
@@ -153,8 +255,17 @@ pub fn find_existing_binary(binary_name: &str) -> Option<PathBuf> {
     which_binary(&exe_name)
 }
 
+/// Whether bin_dir() is one of the directories on PATH.
+pub fn bin_dir_on_path() -> bool {
+    let bin = bin_dir();
+    let bin = bin.canonicalize().unwrap_or(bin);
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| dir.canonicalize().unwrap_or(dir) == bin)
+    })
+}
+
 /// Look up a binary on the system PATH.
-fn which_binary(name: &str) -> Option<PathBuf> {
+pub fn which_binary(name: &str) -> Option<PathBuf> {
     std::env::var_os("PATH").and_then(|paths| {
         std::env::split_paths(&paths)
             .map(|dir| dir.join(name))
@@ -166,7 +277,12 @@ fn which_binary(name: &str) -> Option<PathBuf> {
 /// e.g., "iii-console-aarch64-apple-darwin.sha256"
 /// Note: taiki-e produces checksums as separate assets WITHOUT the archive extension.
 pub fn checksum_asset_name(binary_name: &str) -> String {
-    format!("{}-{}.sha256", binary_name, current_target())
+    checksum_asset_name_for_target(binary_name, current_target())
+}
+
+/// Constructs the expected checksum asset filename for a binary on any target triple.
+pub fn checksum_asset_name_for_target(binary_name: &str, target: &str) -> String {
+    format!("{}-{}.sha256", binary_name, target)
 }
 
 /// Ensures the storage directories exist.
@@ -212,6 +328,22 @@ mod tests {
         assert!(name.ends_with(archive_extension()));
     }
 
+    #[test]
+    fn test_asset_name_for_target() {
+        assert_eq!(
+            asset_name_for_target("iii", "x86_64-pc-windows-msvc"),
+            "iii-x86_64-pc-windows-msvc.zip"
+        );
+        assert_eq!(
+            asset_name_for_target("iii", "aarch64-apple-darwin"),
+            "iii-aarch64-apple-darwin.tar.gz"
+        );
+        assert_eq!(
+            checksum_asset_name_for_target("iii", "aarch64-apple-darwin"),
+            "iii-aarch64-apple-darwin.sha256"
+        );
+    }
+
     #[test]
     fn test_data_dir_not_empty() {
         assert!(!data_dir().as_os_str().is_empty());
@@ -223,6 +355,13 @@ mod tests {
         assert!(path.to_str().unwrap().contains("iii-console"));
     }
 
+    #[test]
+    fn test_versioned_binary_path_format() {
+        let path = versioned_binary_path("iii", &semver::Version::new(0, 4, 1));
+        assert!(path.starts_with(versions_dir()));
+        assert!(path.parent().unwrap().ends_with("iii/0.4.1"));
+    }
+
     #[test]
     fn test_platform_support_check() {
         use crate::registry::REGISTRY;
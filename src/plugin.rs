@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::platform;
+
+/// Executables named `iii-cli-<name>` are run as `iii-cli <name>`.
+pub const PLUGIN_PREFIX: &str = "iii-cli-";
+
+/// An external subcommand found on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    /// Subcommand name (the executable name without the prefix)
+    pub name: String,
+    /// Full path to the executable
+    pub path: PathBuf,
+}
+
+/// Find plugins in the managed bin dir, then on PATH.
+///
+/// When the same plugin exists in several directories the first one wins,
+/// matching how a shell would resolve it. Results are sorted by name.
+pub fn discover() -> Vec<Plugin> {
+    let mut dirs = vec![platform::bin_dir()];
+    if let Some(paths) = std::env::var_os("PATH") {
+        dirs.extend(std::env::split_paths(&paths));
+    }
+    discover_in(&dirs)
+}
+
+/// Find plugins in the given directories, in priority order.
+pub fn discover_in(dirs: &[PathBuf]) -> Vec<Plugin> {
+    let mut seen = HashSet::new();
+    let mut plugins = Vec::new();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = plugin_name(&path) else {
+                continue;
+            };
+            if is_executable(&path) && seen.insert(name.clone()) {
+                plugins.push(Plugin { name, path });
+            }
+        }
+    }
+
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+/// Subcommand name for an executable path, if it follows the plugin naming scheme.
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let stem = if cfg!(target_os = "windows") {
+        let (stem, ext) = file_name.rsplit_once('.')?;
+        if !ext.eq_ignore_ascii_case("exe") {
+            return None;
+        }
+        stem
+    } else {
+        file_name
+    };
+
+    let name = stem.strip_prefix(PLUGIN_PREFIX)?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| name.to_string())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path).map(|m| m.is_file()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn write_executable(dir: &Path, name: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join(name);
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_name() {
+        assert_eq!(plugin_name(Path::new("/bin/iii-cli-deploy")), Some("deploy".to_string()));
+        assert_eq!(
+            plugin_name(Path::new("/bin/iii-cli-seed-data")),
+            Some("seed-data".to_string())
+        );
+        assert_eq!(plugin_name(Path::new("/bin/iii-cli")), None);
+        assert_eq!(plugin_name(Path::new("/bin/iii-cli-")), None);
+        assert_eq!(plugin_name(Path::new("/bin/iii-console")), None);
+        assert_eq!(plugin_name(Path::new("/bin/iii-cli-deploy.tmp")), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_in_first_directory_wins() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let deploy = write_executable(first.path(), "iii-cli-deploy");
+        write_executable(second.path(), "iii-cli-deploy");
+        let seed = write_executable(second.path(), "iii-cli-seed");
+
+        let plugins = discover_in(&[first.path().to_path_buf(), second.path().to_path_buf()]);
+        assert_eq!(
+            plugins,
+            vec![
+                Plugin { name: "deploy".to_string(), path: deploy },
+                Plugin { name: "seed".to_string(), path: seed },
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_in_skips_non_executables() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("iii-cli-notes"), "text").unwrap();
+        std::fs::create_dir(dir.path().join("iii-cli-dir")).unwrap();
+
+        assert!(discover_in(&[dir.path().to_path_buf()]).is_empty());
+    }
+
+    #[test]
+    fn test_discover_in_missing_directory() {
+        assert!(discover_in(&[PathBuf::from("/nonexistent/iii-cli-plugins")]).is_empty());
+    }
+}
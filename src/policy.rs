@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use reqwest::Url;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+
+use crate::error::PolicyError;
+use crate::registry::{self, BinarySpec};
+
+/// Raw contents of the system policy file.
+///
+/// ```toml
+/// allowed_repos = ["iii-hq/*", "acme/iii-fork"]
+/// allowed_mirrors = ["https://github.com/iii-hq/", "https://mirror.acme.internal/"]
+/// require_checksums = true
+/// self_update = false
+///
+/// [forbidden_versions]
+/// iii-console = ">=0.3.0, <0.3.2"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    allowed_repos: Option<Vec<String>>,
+    allowed_mirrors: Option<Vec<String>>,
+    #[serde(default)]
+    require_checksums: bool,
+    #[serde(default = "default_self_update")]
+    self_update: bool,
+    #[serde(default)]
+    forbidden_versions: BTreeMap<String, String>,
+}
+
+fn default_self_update() -> bool {
+    true
+}
+
+/// Restrictions set by administrators in the system policy file. They apply
+/// on top of, and cannot be relaxed by, user or project config.
+#[derive(Debug)]
+pub struct Policy {
+    /// File the policy was read from (None when no policy file exists)
+    path: Option<PathBuf>,
+    /// GitHub repositories binaries may come from: "owner/repo" or "owner/*"
+    allowed_repos: Option<Vec<String>>,
+    /// URL prefixes that downloads and the registry manifest must start with
+    allowed_mirrors: Option<Vec<Url>>,
+    /// Refuse to install archives that cannot be checksum-verified
+    require_checksums: bool,
+    /// Whether iii-cli may update itself
+    self_update: bool,
+    /// Forbidden version ranges keyed by binary name
+    forbidden_versions: BTreeMap<String, VersionReq>,
+}
+
+impl Default for Policy {
+    /// No restrictions.
+    fn default() -> Self {
+        Self {
+            path: None,
+            allowed_repos: None,
+            allowed_mirrors: None,
+            require_checksums: false,
+            self_update: true,
+            forbidden_versions: BTreeMap::new(),
+        }
+    }
+}
+
+impl Policy {
+    /// Load the policy file. A missing file means no restrictions.
+    pub fn load(path: &Path) -> Result<Self, PolicyError> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(PolicyError::Read {
+                    path: path.display().to_string(),
+                    source: e,
+                })
+            }
+        };
+        Self::parse(path, &content)
+    }
+
+    /// Parse policy file contents. Tools in `forbidden_versions` may be named
+    /// by binary or by command; unknown names are kept as binary names so a
+    /// policy can cover tools the registry does not list yet.
+    pub fn parse(path: &Path, content: &str) -> Result<Self, PolicyError> {
+        let file: PolicyFile = toml::from_str(content).map_err(|e| PolicyError::Parse {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+
+        let mut forbidden_versions = BTreeMap::new();
+        for (tool, range) in file.forbidden_versions {
+            let req = VersionReq::parse(&range).map_err(|e| PolicyError::InvalidRange {
+                tool: tool.clone(),
+                range: range.clone(),
+                reason: e.to_string(),
+            })?;
+            let name = registry::resolve_binary_for_update(&tool)
+                .map(|spec| spec.name.to_string())
+                .unwrap_or(tool);
+            forbidden_versions.insert(name, req);
+        }
+
+        let allowed_mirrors = file
+            .allowed_mirrors
+            .map(|mirrors| {
+                mirrors
+                    .iter()
+                    .map(|mirror| {
+                        Url::parse(mirror).map_err(|e| PolicyError::Parse {
+                            path: path.display().to_string(),
+                            message: format!("invalid allowed_mirrors entry '{}': {}", mirror, e),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            allowed_repos: file.allowed_repos,
+            allowed_mirrors,
+            require_checksums: file.require_checksums,
+            self_update: file.self_update,
+            forbidden_versions,
+        })
+    }
+
+    fn source(&self) -> String {
+        self.path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default()
+    }
+
+    /// The policy file in effect, if one was found.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Check that a binary's repository is allowed.
+    pub fn check_repo(&self, spec: &BinarySpec) -> Result<(), PolicyError> {
+        let Some(allowed) = &self.allowed_repos else {
+            return Ok(());
+        };
+        if allowed.iter().any(|pattern| repo_matches(pattern, spec.repo)) {
+            return Ok(());
+        }
+        Err(PolicyError::RepoNotAllowed {
+            binary: spec.name.to_string(),
+            repo: spec.repo.to_string(),
+            policy: self.source(),
+        })
+    }
+
+    /// Check that a download URL points at an allowed mirror: same scheme,
+    /// host and port, under the mirror's path.
+    pub fn check_url(&self, url: &str) -> Result<(), PolicyError> {
+        let Some(allowed) = &self.allowed_mirrors else {
+            return Ok(());
+        };
+        if let Ok(parsed) = Url::parse(url) {
+            if allowed.iter().any(|mirror| is_under(&parsed, mirror)) {
+                return Ok(());
+            }
+        }
+        Err(PolicyError::MirrorNotAllowed {
+            url: url.to_string(),
+            policy: self.source(),
+        })
+    }
+
+    /// Check that an archive without a checksum to verify may be installed.
+    pub fn check_unverified(&self, binary: &str) -> Result<(), PolicyError> {
+        if !self.require_checksums {
+            return Ok(());
+        }
+        Err(PolicyError::ChecksumRequired {
+            binary: binary.to_string(),
+            policy: self.source(),
+        })
+    }
+
+    /// Whether a version of a binary is forbidden.
+    pub fn is_forbidden(&self, binary: &str, version: &Version) -> bool {
+        self.forbidden_versions
+            .get(binary)
+            .is_some_and(|req| req.matches(version))
+    }
+
+    /// Check that a binary comes from an allowed repository and that the
+    /// version is not forbidden.
+    pub fn check_version(&self, spec: &BinarySpec, version: &Version) -> Result<(), PolicyError> {
+        self.check_repo(spec)?;
+        match self.forbidden_versions.get(spec.name) {
+            Some(req) if req.matches(version) => Err(PolicyError::VersionForbidden {
+                binary: spec.name.to_string(),
+                version: version.clone(),
+                range: req.to_string(),
+                policy: self.source(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether iii-cli may update itself.
+    pub fn self_update_allowed(&self) -> bool {
+        self.self_update
+    }
+
+    /// Check that iii-cli may update itself.
+    pub fn check_self_update(&self) -> Result<(), PolicyError> {
+        if self.self_update {
+            return Ok(());
+        }
+        Err(PolicyError::SelfUpdateDisabled {
+            policy: self.source(),
+        })
+    }
+}
+
+/// Match "owner/repo" exactly or every repo of an owner with "owner/*".
+/// GitHub names are case-insensitive.
+fn repo_matches(pattern: &str, repo: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(owner) => repo
+            .split_once('/')
+            .is_some_and(|(repo_owner, _)| repo_owner.eq_ignore_ascii_case(owner)),
+        None => pattern.eq_ignore_ascii_case(repo),
+    }
+}
+
+static CURRENT: OnceLock<Policy> = OnceLock::new();
+
+/// Install the policy for this process. Later calls are ignored.
+pub fn init(policy: Policy) {
+    let _ = CURRENT.set(policy);
+}
+
+/// The policy for this process (unrestricted if `init` was never called).
+pub fn current() -> &'static Policy {
+    CURRENT.get_or_init(Policy::default)
+}
+
+/// Whether `url` is on the same origin as `mirror` and under its path. The
+/// path must match whole segments, so `/iii` does not allow `/iii-fork`.
+fn is_under(url: &Url, mirror: &Url) -> bool {
+    if url.scheme() != mirror.scheme()
+        || url.host_str() != mirror.host_str()
+        || url.port_or_known_default() != mirror.port_or_known_default()
+    {
+        return false;
+    }
+    let prefix = mirror.path().trim_end_matches('/');
+    url.path()
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(content: &str) -> Policy {
+        Policy::parse(Path::new("/etc/iii-cli/policy.toml"), content).unwrap()
+    }
+
+    fn spec(name: &str) -> &'static BinarySpec {
+        registry::resolve_binary_for_update(name).unwrap()
+    }
+
+    #[test]
+    fn test_default_allows_everything() {
+        let policy = Policy::default();
+        assert!(policy.path().is_none());
+        assert!(policy.check_repo(spec("iii-console")).is_ok());
+        assert!(policy.check_url("https://example.com/x.tar.gz").is_ok());
+        assert!(policy.check_unverified("iii-console").is_ok());
+        assert!(policy.check_self_update().is_ok());
+    }
+
+    #[test]
+    fn test_allowed_repos() {
+        let console = spec("iii-console");
+        assert!(policy(&format!("allowed_repos = [\"{}\"]", console.repo))
+            .check_repo(console)
+            .is_ok());
+        let owner = console.repo.split('/').next().unwrap();
+        assert!(policy(&format!("allowed_repos = [\"{}/*\"]", owner.to_uppercase()))
+            .check_repo(console)
+            .is_ok());
+        assert!(matches!(
+            policy("allowed_repos = [\"acme/*\"]").check_repo(console),
+            Err(PolicyError::RepoNotAllowed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_allowed_mirrors() {
+        let acme = policy("allowed_mirrors = [\"https://mirror.acme.internal/\"]");
+        assert!(acme
+            .check_url("https://mirror.acme.internal/iii/iii.tar.gz")
+            .is_ok());
+        assert!(matches!(
+            acme.check_url("https://github.com/iii-hq/iii/releases/download/x"),
+            Err(PolicyError::MirrorNotAllowed { .. })
+        ));
+
+        let corp = policy("allowed_mirrors = [\"https://mirror.corp.example.com/iii\"]");
+        assert!(corp.check_url("https://mirror.corp.example.com/iii/iii.tar.gz").is_ok());
+        assert!(corp.check_url("https://MIRROR.corp.example.com/iii/x").is_ok());
+        for url in [
+            "https://mirror.corp.example.com.evil.net/iii/x",
+            "https://mirror.corp.example.com@evil.net/iii/x",
+            "https://mirror.corp.example.com:8443/iii/x",
+            "http://mirror.corp.example.com/iii/x",
+            "https://mirror.corp.example.com/iii-fork/x",
+            "https://mirror.corp.example.com/iii/../other/x",
+            "not a url",
+        ] {
+            assert!(corp.check_url(url).is_err(), "{}", url);
+        }
+        assert!(matches!(
+            Policy::parse(Path::new("policy.toml"), "allowed_mirrors = [\"mirror\"]\n"),
+            Err(PolicyError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_forbidden_versions_accept_command_names() {
+        let console = spec("console");
+        let policy = policy("[forbidden_versions]\nconsole = \">=0.3.0, <0.3.2\"\n");
+        assert!(policy.is_forbidden(console.name, &Version::new(0, 3, 1)));
+        assert!(matches!(
+            policy.check_version(console, &Version::new(0, 3, 1)),
+            Err(PolicyError::VersionForbidden { .. })
+        ));
+        assert!(policy.check_version(console, &Version::new(0, 3, 2)).is_ok());
+    }
+
+    #[test]
+    fn test_checksums_and_self_update() {
+        let policy = policy("require_checksums = true\nself_update = false\n");
+        assert!(matches!(
+            policy.check_unverified("iii-console"),
+            Err(PolicyError::ChecksumRequired { .. })
+        ));
+        assert!(matches!(
+            policy.check_self_update(),
+            Err(PolicyError::SelfUpdateDisabled { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_policy_is_rejected() {
+        let path = Path::new("policy.toml");
+        assert!(matches!(
+            Policy::parse(path, "[forbidden_versions]\niii = \"not a range\"\n"),
+            Err(PolicyError::InvalidRange { .. })
+        ));
+        assert!(matches!(
+            Policy::parse(path, "require_checksum = true\n"),
+            Err(PolicyError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_missing_file_is_unrestricted() {
+        let policy = Policy::load(Path::new("/nonexistent/iii-cli/policy.toml")).unwrap();
+        assert!(policy.path().is_none());
+    }
+}
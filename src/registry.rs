@@ -1,3 +1,9 @@
+use std::sync::OnceLock;
+
+use colored::Colorize;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
 use crate::error::RegistryError;
 
 /// Specification for a managed binary
@@ -22,8 +28,38 @@ pub struct CommandMapping {
     pub cli_command: &'static str,
     /// The subcommand to pass to the binary, or None for direct passthrough
     pub binary_subcommand: Option<&'static str>,
+    /// One-line description shown in `iii-cli --help`
+    pub about: &'static str,
+    /// Parent command the command is nested under (e.g., "sdk" for "sdk motia"),
+    /// or None for a top-level command
+    pub namespace: Option<&'static str>,
+}
+
+impl CommandMapping {
+    /// The user-facing command path (e.g., "console", "sdk motia").
+    pub fn path(&self) -> String {
+        match self.namespace {
+            Some(namespace) => format!("{} {}", namespace, self.cli_command),
+            None => self.cli_command.to_string(),
+        }
+    }
 }
 
+/// A command namespace grouping related commands (e.g., "sdk").
+#[derive(Debug, Clone)]
+pub struct Namespace {
+    pub name: &'static str,
+    /// One-line description shown in `iii-cli --help`
+    pub about: &'static str,
+}
+
+/// Descriptions for known namespaces. Namespaces introduced by the remote
+/// registry without an entry here get a generic description.
+pub static NAMESPACES: &[Namespace] = &[Namespace {
+    name: "sdk",
+    about: "Manage SDKs powered by Motia",
+}];
+
 /// Specification for iii-cli itself (the dispatcher).
 /// Kept separate from REGISTRY because iii-cli is not a dispatched binary.
 pub static SELF_SPEC: BinarySpec = BinarySpec {
@@ -42,7 +78,8 @@ pub static SELF_SPEC: BinarySpec = BinarySpec {
     commands: &[],
 };
 
-/// The compiled-in binary registry
+/// The compiled-in binary registry.
+/// Used as-is when no remote registry manifest is cached (offline fallback).
 pub static REGISTRY: &[BinarySpec] = &[
     BinarySpec {
         name: "iii-console",
@@ -60,6 +97,8 @@ pub static REGISTRY: &[BinarySpec] = &[
         commands: &[CommandMapping {
             cli_command: "console",
             binary_subcommand: None,
+            about: "Launch the iii web console",
+            namespace: None,
         }],
     },
     BinarySpec {
@@ -76,6 +115,8 @@ pub static REGISTRY: &[BinarySpec] = &[
         commands: &[CommandMapping {
             cli_command: "create",
             binary_subcommand: Some("create"),
+            about: "Create a new iii project from a template",
+            namespace: None,
         }],
     },
     BinarySpec {
@@ -95,6 +136,8 @@ pub static REGISTRY: &[BinarySpec] = &[
         commands: &[CommandMapping {
             cli_command: "motia",
             binary_subcommand: None,
+            about: "Motia SDK tools",
+            namespace: Some("sdk"),
         }],
     },
     BinarySpec {
@@ -114,37 +157,215 @@ pub static REGISTRY: &[BinarySpec] = &[
         commands: &[CommandMapping {
             cli_command: "start",
             binary_subcommand: None,
+            about: "Start the iii process communication engine",
+            namespace: None,
         }],
     },
 ];
 
-/// Resolve a CLI command name to its BinarySpec and optional binary subcommand.
-pub fn resolve_command(command: &str) -> Result<(&'static BinarySpec, Option<&'static str>), RegistryError> {
-    for spec in REGISTRY {
+/// The effective registry: REGISTRY with the cached remote manifest merged over it.
+static MERGED: OnceLock<Vec<BinarySpec>> = OnceLock::new();
+
+/// Entry for a binary in a remote registry manifest.
+/// Carries the same fields as BinarySpec.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteBinarySpec {
+    pub name: String,
+    pub repo: String,
+    #[serde(default)]
+    pub has_checksum: bool,
+    pub supported_targets: Vec<String>,
+    #[serde(default)]
+    pub commands: Vec<RemoteCommandMapping>,
+}
+
+/// Entry for a command mapping in a remote registry manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteCommandMapping {
+    pub cli_command: String,
+    #[serde(default)]
+    pub binary_subcommand: Option<String>,
+    #[serde(default)]
+    pub about: Option<String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// Install the effective registry for this process, merging remote entries
+/// over the compiled-in REGISTRY. Entries replace built-ins with the same
+/// binary name; new binaries are appended. Entries whose commands clash with
+/// a built-in command or another binary's are skipped with a warning. Must be
+/// called once at startup, before any lookup; later calls are ignored.
+pub fn init(remote: &[RemoteBinarySpec]) {
+    let mut merged: Vec<BinarySpec> = REGISTRY.to_vec();
+    for entry in remote {
+        let others: Vec<&BinarySpec> = merged.iter().filter(|s| s.name != entry.name).collect();
+        if let Err(reason) = entry.check_commands(&others) {
+            eprintln!(
+                "  {} skipping registry entry for {}: {}",
+                "warning:".yellow(),
+                entry.name,
+                reason
+            );
+            continue;
+        }
+        let spec = entry.to_static();
+        match merged.iter_mut().find(|s| s.name == spec.name) {
+            Some(existing) => *existing = spec,
+            None => merged.push(spec),
+        }
+    }
+    let _ = MERGED.set(merged);
+}
+
+/// The effective registry (compiled-in REGISTRY unless `init` merged a remote manifest).
+pub fn specs() -> &'static [BinarySpec] {
+    MERGED.get().map(Vec::as_slice).unwrap_or(REGISTRY)
+}
+
+impl RemoteBinarySpec {
+    /// Check that an entry is safe to use. Binary names become file names
+    /// under bin_dir(), so they are restricted to a conservative character set.
+    pub fn validate(&self) -> Result<(), String> {
+        let is_safe = |s: &str| {
+            !s.is_empty()
+                && !s.starts_with('.')
+                && s.chars().all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+        };
+        if !is_safe(&self.name) {
+            return Err(format!("invalid binary name '{}'", self.name));
+        }
+        if self.name == SELF_SPEC.name {
+            return Err("iii-cli cannot be overridden by the registry".to_string());
+        }
+        match self.repo.split_once('/') {
+            Some((owner, repo)) if is_safe(owner) && is_safe(repo) => {}
+            _ => return Err(format!("invalid repo '{}' for {}", self.repo, self.name)),
+        }
+        if let Some(cmd) = self.commands.iter().find(|c| {
+            !is_safe(&c.cli_command) || c.namespace.as_deref().is_some_and(|n| !is_safe(n))
+        }) {
+            return Err(format!("invalid command '{}' for {}", cmd.cli_command, self.name));
+        }
+        Ok(())
+    }
+
+    /// Check that the entry's commands are free: not a built-in command, not
+    /// used twice, and not used by another binary. A command name also can't
+    /// be a namespace, since both are top-level subcommands.
+    pub fn check_commands(&self, others: &[&BinarySpec]) -> Result<(), String> {
+        let mut taken: Vec<(Option<&str>, &str)> = others
+            .iter()
+            .flat_map(|s| s.commands.iter())
+            .map(|m| (m.namespace, m.cli_command))
+            .collect();
+        for command in &self.commands {
+            let key = (command.namespace.as_deref(), command.cli_command.as_str());
+            let path = match key.0 {
+                Some(namespace) => format!("{} {}", namespace, key.1),
+                None => key.1.to_string(),
+            };
+            let top_level = key.0.unwrap_or(key.1);
+            if crate::cli::is_builtin(top_level) {
+                return Err(format!("'{}' is a built-in command", top_level));
+            }
+            let clashes = |&(namespace, name): &(Option<&str>, &str)| match key.0 {
+                Some(ns) => namespace.is_none() && name == ns,
+                None => namespace == Some(key.1),
+            };
+            if taken.contains(&key) || taken.iter().any(clashes) {
+                return Err(format!("command '{}' is already taken", path));
+            }
+            taken.push(key);
+        }
+        Ok(())
+    }
+
+    /// Convert to a BinarySpec. The strings are leaked: the registry lives for
+    /// the whole process, and this keeps `&'static BinarySpec` lookups working.
+    fn to_static(&self) -> BinarySpec {
+        fn leak(s: &str) -> &'static str {
+            Box::leak(s.to_string().into_boxed_str())
+        }
+
+        let targets: Vec<&'static str> = self.supported_targets.iter().map(|t| leak(t)).collect();
+        let commands: Vec<CommandMapping> = self
+            .commands
+            .iter()
+            .map(|c| CommandMapping {
+                cli_command: leak(&c.cli_command),
+                binary_subcommand: c.binary_subcommand.as_deref().map(leak),
+                about: match &c.about {
+                    Some(about) => leak(about),
+                    None => leak(&format!("Run {}", self.name)),
+                },
+                namespace: c.namespace.as_deref().map(leak),
+            })
+            .collect();
+
+        BinarySpec {
+            name: leak(&self.name),
+            repo: leak(&self.repo),
+            has_checksum: self.has_checksum,
+            supported_targets: Box::leak(targets.into_boxed_slice()),
+            commands: Box::leak(commands.into_boxed_slice()),
+        }
+    }
+}
+
+/// Resolve a CLI command, nested under `namespace` or top-level, to its
+/// BinarySpec and optional binary subcommand.
+pub fn resolve_command(
+    namespace: Option<&str>,
+    command: &str,
+) -> Result<(&'static BinarySpec, Option<&'static str>), RegistryError> {
+    find_command(specs(), namespace, command)
+}
+
+fn find_command(
+    specs: &'static [BinarySpec],
+    namespace: Option<&str>,
+    command: &str,
+) -> Result<(&'static BinarySpec, Option<&'static str>), RegistryError> {
+    for spec in specs {
         for mapping in spec.commands {
-            if mapping.cli_command == command {
+            if mapping.namespace == namespace && mapping.cli_command == command {
                 return Ok((spec, mapping.binary_subcommand));
             }
         }
     }
     Err(RegistryError::UnknownCommand {
-        command: command.to_string(),
+        command: match namespace {
+            Some(namespace) => format!("{} {}", namespace, command),
+            None => command.to_string(),
+        },
     })
 }
 
 /// Resolve a command name to its parent BinarySpec (for update resolution).
-/// e.g., "create" resolves to iii-tools.
+/// e.g., "create" resolves to iii-tools. A command path ("sdk motia") or a
+/// top-level command wins over a namespaced command with the same name.
 pub fn resolve_binary_for_update(command: &str) -> Result<&'static BinarySpec, RegistryError> {
+    find_binary_for_update(specs(), command)
+}
+
+fn find_binary_for_update(
+    specs: &'static [BinarySpec],
+    command: &str,
+) -> Result<&'static BinarySpec, RegistryError> {
     // First try exact binary name match
-    for spec in REGISTRY {
+    for spec in specs {
         if spec.name == command {
             return Ok(spec);
         }
     }
-    // Then try command name match
-    for spec in REGISTRY {
-        for mapping in spec.commands {
-            if mapping.cli_command == command {
+    // Then the command path, then the command name under any namespace
+    for matches in [
+        |m: &CommandMapping, command: &str| m.path() == command,
+        |m: &CommandMapping, command: &str| m.cli_command == command,
+    ] {
+        for spec in specs {
+            if spec.commands.iter().any(|m| matches(m, command)) {
                 return Ok(spec);
             }
         }
@@ -154,32 +375,118 @@ pub fn resolve_binary_for_update(command: &str) -> Result<&'static BinarySpec, R
     })
 }
 
+/// Split a `<command|binary>@<version>` target into its name and optional version.
+/// e.g., "start@0.4.2" yields ("start", Some(0.4.2)). A leading 'v' is accepted.
+pub fn parse_versioned_target(target: &str) -> Result<(&str, Option<Version>), RegistryError> {
+    match target.split_once('@') {
+        None => Ok((target, None)),
+        Some((name, version)) => Ok((name, Some(parse_version(version)?))),
+    }
+}
+
+/// Parse a user-supplied version string (strips leading 'v' if present).
+pub fn parse_version(version: &str) -> Result<Version, RegistryError> {
+    let cleaned = version.strip_prefix('v').unwrap_or(version);
+    Version::parse(cleaned).map_err(|e| RegistryError::InvalidVersion {
+        version: version.to_string(),
+        reason: e.to_string(),
+    })
+}
+
 /// Get all unique BinarySpecs in the registry.
 pub fn all_binaries() -> Vec<&'static BinarySpec> {
-    REGISTRY.iter().collect()
+    specs().iter().collect()
 }
 
 /// List all available CLI command names (using user-facing paths).
-pub fn available_commands() -> Vec<&'static str> {
-    REGISTRY
+pub fn available_commands() -> Vec<String> {
+    specs()
         .iter()
-        .flat_map(|spec| spec.commands.iter().map(|m| {
-            if m.cli_command == "motia" {
-                "sdk motia"
-            } else {
-                m.cli_command
-            }
-        }))
+        .flat_map(|spec| spec.commands.iter().map(CommandMapping::path))
         .collect()
 }
 
+/// The user-facing command path for a binary's primary command
+/// (e.g., "sdk motia" for motia-cli).
+pub fn command_path_for_binary(binary_name: &str) -> Option<String> {
+    specs()
+        .iter()
+        .find(|s| s.name == binary_name)
+        .and_then(|s| s.commands.first())
+        .map(CommandMapping::path)
+}
+
+/// The primary command name for a binary, as accepted by `iii-cli update`
+/// (e.g., "motia" for motia-cli).
+pub fn primary_command(binary_name: &str) -> Option<&'static str> {
+    specs()
+        .iter()
+        .find(|s| s.name == binary_name)
+        .and_then(|s| s.commands.first())
+        .map(|c| c.cli_command)
+}
+
+/// All command mappings in the effective registry.
+pub fn command_mappings() -> impl Iterator<Item = &'static CommandMapping> {
+    specs().iter().flat_map(|spec| spec.commands.iter())
+}
+
+/// Names of all namespaces used by the effective registry, in registry order.
+pub fn namespaces() -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = Vec::new();
+    for namespace in command_mappings().filter_map(|m| m.namespace) {
+        if !names.contains(&namespace) {
+            names.push(namespace);
+        }
+    }
+    names
+}
+
+/// Description for a namespace.
+pub fn namespace_about(name: &str) -> String {
+    NAMESPACES
+        .iter()
+        .find(|n| n.name == name)
+        .map(|n| n.about.to_string())
+        .unwrap_or_else(|| format!("{} commands", name))
+}
+
+/// Resolve an update/install target to its BinarySpec.
+///
+/// Accepts binary names ("iii-console"), commands ("console"), command paths
+/// ("sdk motia"), "self"/"iii-cli" for iii-cli itself, and namespaces that
+/// contain a single binary ("sdk").
+pub fn resolve_update_target(target: &str) -> Result<&'static BinarySpec, RegistryError> {
+    if matches!(target, "iii-cli" | "self") {
+        return Ok(&SELF_SPEC);
+    }
+    if let Ok(spec) = resolve_binary_for_update(target) {
+        return Ok(spec);
+    }
+
+    let in_namespace: Vec<&'static BinarySpec> = specs()
+        .iter()
+        .filter(|spec| {
+            spec.commands
+                .iter()
+                .any(|m| m.namespace == Some(target) || m.path() == target)
+        })
+        .collect();
+    match in_namespace.as_slice() {
+        [spec] => Ok(spec),
+        _ => Err(RegistryError::UnknownCommand {
+            command: target.to_string(),
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_resolve_console() {
-        let (spec, sub) = resolve_command("console").unwrap();
+        let (spec, sub) = resolve_command(None, "console").unwrap();
         assert_eq!(spec.name, "iii-console");
         assert_eq!(spec.repo, "iii-hq/console");
         assert!(sub.is_none());
@@ -187,7 +494,7 @@ mod tests {
 
     #[test]
     fn test_resolve_create() {
-        let (spec, sub) = resolve_command("create").unwrap();
+        let (spec, sub) = resolve_command(None, "create").unwrap();
         assert_eq!(spec.name, "iii-tools");
         assert_eq!(spec.repo, "iii-hq/cli-tooling");
         assert_eq!(sub, Some("create"));
@@ -195,7 +502,7 @@ mod tests {
 
     #[test]
     fn test_resolve_motia() {
-        let (spec, sub) = resolve_command("motia").unwrap();
+        let (spec, sub) = resolve_command(Some("sdk"), "motia").unwrap();
         assert_eq!(spec.name, "motia-cli");
         assert_eq!(spec.repo, "MotiaDev/motia-cli");
         assert!(sub.is_none());
@@ -203,13 +510,13 @@ mod tests {
 
     #[test]
     fn test_motia_no_checksum() {
-        let (spec, _) = resolve_command("motia").unwrap();
+        let (spec, _) = resolve_command(Some("sdk"), "motia").unwrap();
         assert!(!spec.has_checksum);
     }
 
     #[test]
     fn test_resolve_start() {
-        let (spec, sub) = resolve_command("start").unwrap();
+        let (spec, sub) = resolve_command(None, "start").unwrap();
         assert_eq!(spec.name, "iii");
         assert_eq!(spec.repo, "iii-hq/iii");
         assert!(sub.is_none());
@@ -217,13 +524,13 @@ mod tests {
 
     #[test]
     fn test_start_no_checksum() {
-        let (spec, _) = resolve_command("start").unwrap();
+        let (spec, _) = resolve_command(None, "start").unwrap();
         assert!(!spec.has_checksum);
     }
 
     #[test]
     fn test_unknown_command() {
-        assert!(resolve_command("foobar").is_err());
+        assert!(resolve_command(None, "foobar").is_err());
     }
 
     #[test]
@@ -238,11 +545,35 @@ mod tests {
     #[test]
     fn test_available_commands() {
         let cmds = available_commands();
-        assert!(cmds.contains(&"console"));
-        assert!(cmds.contains(&"create"));
-        assert!(cmds.contains(&"sdk motia"));
-        assert!(cmds.contains(&"start"));
-        assert!(!cmds.contains(&"motia"), "motia should be namespaced as 'sdk motia'");
+        assert!(cmds.contains(&"console".to_string()));
+        assert!(cmds.contains(&"create".to_string()));
+        assert!(cmds.contains(&"sdk motia".to_string()));
+        assert!(cmds.contains(&"start".to_string()));
+        assert!(!cmds.contains(&"motia".to_string()), "motia should be namespaced as 'sdk motia'");
+    }
+
+    #[test]
+    fn test_command_path_for_binary() {
+        assert_eq!(command_path_for_binary("motia-cli").as_deref(), Some("sdk motia"));
+        assert_eq!(command_path_for_binary("iii-console").as_deref(), Some("console"));
+        assert!(command_path_for_binary("nonexistent").is_none());
+        assert_eq!(primary_command("motia-cli"), Some("motia"));
+    }
+
+    #[test]
+    fn test_namespaces() {
+        assert_eq!(namespaces(), vec!["sdk"]);
+        assert_eq!(namespace_about("sdk"), "Manage SDKs powered by Motia");
+        assert_eq!(namespace_about("cloud"), "cloud commands");
+    }
+
+    #[test]
+    fn test_resolve_update_target() {
+        assert_eq!(resolve_update_target("sdk").unwrap().name, "motia-cli");
+        assert_eq!(resolve_update_target("sdk motia").unwrap().name, "motia-cli");
+        assert_eq!(resolve_update_target("self").unwrap().name, "iii-cli");
+        assert_eq!(resolve_update_target("console").unwrap().name, "iii-console");
+        assert!(resolve_update_target("foobar").is_err());
     }
 
     #[test]
@@ -259,13 +590,13 @@ mod tests {
 
     #[test]
     fn test_console_has_checksum() {
-        let (spec, _) = resolve_command("console").unwrap();
+        let (spec, _) = resolve_command(None, "console").unwrap();
         assert!(spec.has_checksum);
     }
 
     #[test]
     fn test_iii_tools_has_checksum() {
-        let (spec, _) = resolve_command("create").unwrap();
+        let (spec, _) = resolve_command(None, "create").unwrap();
         assert!(spec.has_checksum, "iii-tools should have checksums enabled");
     }
 
@@ -296,6 +627,100 @@ mod tests {
         }
     }
 
+    fn remote(name: &str, repo: &str, command: &str) -> RemoteBinarySpec {
+        RemoteBinarySpec {
+            name: name.to_string(),
+            repo: repo.to_string(),
+            has_checksum: true,
+            supported_targets: vec!["x86_64-unknown-linux-musl".to_string()],
+            commands: vec![RemoteCommandMapping {
+                cli_command: command.to_string(),
+                binary_subcommand: None,
+                about: None,
+                namespace: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_remote_spec_to_static() {
+        let spec = remote("iii-deploy", "iii-hq/deploy", "deploy").to_static();
+        assert_eq!(spec.name, "iii-deploy");
+        assert_eq!(spec.repo, "iii-hq/deploy");
+        assert_eq!(spec.supported_targets, &["x86_64-unknown-linux-musl"]);
+        assert_eq!(spec.commands[0].cli_command, "deploy");
+        assert!(spec.commands[0].binary_subcommand.is_none());
+        assert_eq!(spec.commands[0].about, "Run iii-deploy");
+    }
+
+    #[test]
+    fn test_remote_spec_validation() {
+        assert!(remote("iii-deploy", "iii-hq/deploy", "deploy").validate().is_ok());
+        assert!(remote("../evil", "iii-hq/deploy", "deploy").validate().is_err());
+        assert!(remote("iii-cli", "iii-hq/iii-cli", "cli").validate().is_err());
+        assert!(remote("iii-deploy", "not-a-repo", "deploy").validate().is_err());
+        assert!(remote("iii-deploy", "iii-hq/deploy", "de ploy").validate().is_err());
+    }
+
+    #[test]
+    fn test_namespaced_and_top_level_commands_resolve_apart() {
+        let mut merged = REGISTRY.to_vec();
+        merged.push(remote("iii-motia", "iii-hq/motia", "motia").to_static());
+        let merged: &'static [BinarySpec] = Box::leak(merged.into_boxed_slice());
+
+        let (spec, _) = find_command(merged, None, "motia").unwrap();
+        assert_eq!(spec.name, "iii-motia");
+        let (spec, _) = find_command(merged, Some("sdk"), "motia").unwrap();
+        assert_eq!(spec.name, "motia-cli");
+        assert!(find_command(merged, Some("sdk"), "console").is_err());
+
+        assert_eq!(find_binary_for_update(merged, "motia").unwrap().name, "iii-motia");
+        assert_eq!(find_binary_for_update(merged, "sdk motia").unwrap().name, "motia-cli");
+        assert_eq!(find_binary_for_update(REGISTRY, "motia").unwrap().name, "motia-cli");
+    }
+
+    #[test]
+    fn test_remote_commands_must_be_free() {
+        let others: Vec<&BinarySpec> = REGISTRY.iter().collect();
+        assert!(remote("iii-deploy", "iii-hq/deploy", "deploy").check_commands(&others).is_ok());
+        for command in ["update", "cache", "help", "console", "sdk"] {
+            assert!(
+                remote("iii-deploy", "iii-hq/deploy", command).check_commands(&others).is_err(),
+                "{}",
+                command
+            );
+        }
+
+        let mut twice = remote("iii-deploy", "iii-hq/deploy", "deploy");
+        twice.commands.push(twice.commands[0].clone());
+        assert!(twice.check_commands(&others).is_err());
+
+        // Replacing a built-in binary may keep its commands
+        let console: Vec<&BinarySpec> = REGISTRY.iter().filter(|s| s.name != "iii-console").collect();
+        assert!(remote("iii-console", "iii-hq/console", "console").check_commands(&console).is_ok());
+    }
+
+    #[test]
+    fn test_parse_versioned_target() {
+        let (name, version) = parse_versioned_target("start@0.4.2").unwrap();
+        assert_eq!(name, "start");
+        assert_eq!(version, Some(Version::new(0, 4, 2)));
+
+        let (name, version) = parse_versioned_target("iii-console@v0.3.1").unwrap();
+        assert_eq!(name, "iii-console");
+        assert_eq!(version, Some(Version::new(0, 3, 1)));
+
+        let (name, version) = parse_versioned_target("console").unwrap();
+        assert_eq!(name, "console");
+        assert!(version.is_none());
+    }
+
+    #[test]
+    fn test_parse_versioned_target_invalid_version() {
+        assert!(parse_versioned_target("start@latest").is_err());
+        assert!(parse_versioned_target("start@").is_err());
+    }
+
     #[test]
     fn test_self_spec_platform_support() {
         let result = crate::platform::check_platform_support(&SELF_SPEC);
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::download;
+use crate::error::RegistryError;
+use crate::platform;
+use crate::policy;
+use crate::registry::RemoteBinarySpec;
+
+/// Default URL of the remote registry manifest (`registry.url` in config).
+/// A `.sha256` sidecar must be published next to it.
+pub const DEFAULT_REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/iii-hq/iii-cli/main/registry.json";
+
+/// The remote registry manifest document.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RegistryManifest {
+    #[serde(default)]
+    pub binaries: Vec<RemoteBinarySpec>,
+}
+
+impl RegistryManifest {
+    /// Parse and validate a manifest. A manifest with any invalid entry is
+    /// rejected as a whole rather than partially applied.
+    pub fn parse(content: &[u8]) -> Result<Self, RegistryError> {
+        let manifest: Self = serde_json::from_slice(content)
+            .map_err(|e| RegistryError::ManifestInvalid(e.to_string()))?;
+        for entry in &manifest.binaries {
+            entry.validate().map_err(RegistryError::ManifestInvalid)?;
+        }
+        Ok(manifest)
+    }
+}
+
+/// The manifest URL, overridable with `registry.url` (e.g., for an internal mirror).
+pub fn registry_url() -> String {
+    config::current().registry_url()
+}
+
+/// Path of the cached manifest.
+pub fn cache_path() -> PathBuf {
+    platform::data_dir().join("registry.json")
+}
+
+/// Load the cached manifest entries. Returns no entries if there is no cache
+/// or it no longer validates, so the compiled-in registry is used as-is.
+pub fn load_cached() -> Vec<RemoteBinarySpec> {
+    load_from(&cache_path())
+}
+
+fn load_from(path: &Path) -> Vec<RemoteBinarySpec> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|content| RegistryManifest::parse(&content).ok())
+        .map(|manifest| manifest.binaries)
+        .unwrap_or_default()
+}
+
+/// Fetch the manifest and its `.sha256` sidecar, verify it, and replace the cache.
+/// The new entries take effect on the next run. If no manifest is published
+/// (404), the cache is left untouched and an empty manifest is returned.
+pub async fn refresh(client: &reqwest::Client) -> Result<RegistryManifest, RegistryError> {
+    let url = registry_url();
+    policy::current().check_url(&url)?;
+    let fetch_failed = |e: reqwest::Error| RegistryError::ManifestFetch {
+        url: url.clone(),
+        reason: e.to_string(),
+    };
+
+    let response = client.get(&url).send().await.map_err(fetch_failed)?;
+
+    // No manifest published: keep using the cache (or the compiled-in registry)
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(RegistryManifest::default());
+    }
+
+    let content = response
+        .error_for_status()
+        .map_err(fetch_failed)?
+        .bytes()
+        .await
+        .map_err(fetch_failed)?;
+
+    let expected = download::fetch_sidecar_checksum(client, &format!("{}.sha256", url))
+        .await
+        .map_err(|e| RegistryError::ManifestFetch {
+            url: format!("{}.sha256", url),
+            reason: e.to_string(),
+        })?;
+    let actual = download::sha256_hex(&content);
+    if actual != expected {
+        return Err(RegistryError::ManifestChecksumMismatch { expected, actual });
+    }
+
+    let manifest = RegistryManifest::parse(&content)?;
+    write_cache(&cache_path(), &content)
+        .map_err(|e| RegistryError::ManifestInvalid(format!("failed to cache manifest: {}", e)))?;
+    Ok(manifest)
+}
+
+/// Atomically replace the cached manifest.
+fn write_cache(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "binaries": [
+            {
+                "name": "iii-deploy",
+                "repo": "iii-hq/deploy",
+                "has_checksum": true,
+                "supported_targets": ["aarch64-apple-darwin"],
+                "commands": [{ "cli_command": "deploy" }]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = RegistryManifest::parse(MANIFEST.as_bytes()).unwrap();
+        assert_eq!(manifest.binaries.len(), 1);
+        assert_eq!(manifest.binaries[0].commands[0].cli_command, "deploy");
+        assert!(manifest.binaries[0].commands[0].binary_subcommand.is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_entry() {
+        let bad = MANIFEST.replace("iii-deploy", "../../bin/sh");
+        assert!(matches!(
+            RegistryManifest::parse(bad.as_bytes()),
+            Err(RegistryError::ManifestInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_corrupt_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registry.json");
+
+        assert!(load_from(&path).is_empty());
+
+        write_cache(&path, MANIFEST.as_bytes()).unwrap();
+        assert_eq!(load_from(&path).len(), 1);
+
+        std::fs::write(&path, b"{ not json").unwrap();
+        assert!(load_from(&path).is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use reqwest::StatusCode;
+
+use crate::config;
+use crate::error::NetworkError;
+use crate::format;
+
+/// How often and how long to retry requests that failed transiently.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// The policy set by `network.retries`, `network.retry_base_delay_ms`
+    /// and `network.retry_max_delay_secs`.
+    pub fn from_config() -> Self {
+        let config = config::current();
+        Self {
+            retries: config.network_retries(),
+            base_delay: config.retry_base_delay(),
+            max_delay: config.retry_max_delay(),
+        }
+    }
+
+    /// Delay after failed attempt number `attempt` (starting at 1): the base
+    /// delay doubled for each earlier retry, capped at the maximum delay, or
+    /// what the server asked for with `Retry-After`. None when no retries are
+    /// left, or when the server asks to wait longer than the maximum delay,
+    /// since retrying any sooner would fail again.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt > self.retries {
+            return None;
+        }
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1));
+        Some(backoff.min(self.max_delay))
+    }
+}
+
+/// Counts the attempts of one operation and waits between them.
+#[derive(Debug)]
+pub struct Retrier {
+    policy: RetryPolicy,
+    attempt: u32,
+}
+
+impl Retrier {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy, attempt: 1 }
+    }
+
+    /// Attempts made so far, including the current one.
+    pub fn attempts(&self) -> u32 {
+        self.attempt
+    }
+
+    /// After a transient failure, report it and wait before the next
+    /// attempt. Returns false when no retries are left.
+    pub async fn backoff(
+        &mut self,
+        reason: &dyn std::fmt::Display,
+        retry_after: Option<Duration>,
+    ) -> bool {
+        let Some(delay) = self.policy.delay(self.attempt, retry_after) else {
+            return false;
+        };
+        eprintln!(
+            "  {} {}; retrying in {} (attempt {} of {})",
+            "warning:".yellow(),
+            reason,
+            format::format_duration(delay),
+            self.attempt + 1,
+            self.policy.retries + 1
+        );
+        tokio::time::sleep(delay).await;
+        self.attempt += 1;
+        true
+    }
+
+    /// The error for a request that failed on the last attempt. It records
+    /// the number of attempts when there was more than one. A final 429
+    /// means the rate limit is still exhausted.
+    pub fn give_up(&self, source: reqwest::Error) -> NetworkError {
+        if source.status() == Some(StatusCode::TOO_MANY_REQUESTS) {
+            NetworkError::RateLimited
+        } else if self.attempt > 1 {
+            NetworkError::RetriesExhausted {
+                attempts: self.attempt,
+                source,
+            }
+        } else {
+            NetworkError::RequestFailed(source)
+        }
+    }
+
+    /// Send a request, retrying connect errors, 5xx and 429 responses.
+    /// Other responses, including errors like 404, are returned for the
+    /// caller to handle.
+    pub async fn send(
+        &mut self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, NetworkError> {
+        loop {
+            // Requests without a streaming body can always be cloned
+            let attempt = request
+                .try_clone()
+                .expect("request body must be cloneable to retry");
+            let (error, retry_after) = match attempt.send().await {
+                Ok(response) if is_transient_status(response.status()) => {
+                    let retry_after = retry_after(&response);
+                    match response.error_for_status() {
+                        Err(e) => (e, retry_after),
+                        Ok(response) => return Ok(response),
+                    }
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if is_transient(&e) => (e, None),
+                Err(e) => return Err(NetworkError::RequestFailed(e)),
+            };
+            if !self.backoff(&error, retry_after).await {
+                return Err(self.give_up(error));
+            }
+        }
+    }
+}
+
+/// Send a request with the configured retry policy.
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, NetworkError> {
+    Retrier::new(RetryPolicy::from_config()).send(request).await
+}
+
+/// Whether a request error is worth retrying: the connection failed, was
+/// reset, or timed out.
+pub fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_request() || error.is_body()
+}
+
+/// Server errors and rate limiting may succeed on a later attempt.
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// The delay a response asks for with `Retry-After`.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?;
+    parse_retry_after(value, Utc::now())
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_backs_off_exponentially() {
+        let policy = RetryPolicy {
+            retries: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(3),
+        };
+        assert_eq!(policy.delay(1, None), Some(Duration::from_millis(500)));
+        assert_eq!(policy.delay(2, None), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(3, None), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay(4, None), Some(Duration::from_secs(3)));
+        assert_eq!(policy.delay(5, None), None);
+
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn test_no_retries() {
+        let policy = RetryPolicy {
+            retries: 0,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        };
+        assert_eq!(policy.delay(1, None), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn test_transient_statuses() {
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::FORBIDDEN));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_longer_than_max_delay_gives_up() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/releases", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0; 1024]).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3600\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let mut retrier = Retrier::new(RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(30),
+        });
+        let client = reqwest::Client::new();
+        let error = retrier.send(client.get(&url)).await.unwrap_err();
+        assert!(matches!(error, NetworkError::RateLimited));
+        assert_eq!(retrier.attempts(), 1);
+        server.await.unwrap();
+    }
+}
@@ -1,15 +1,36 @@
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, Utc};
+use colored::Colorize;
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::error::StateError;
+use crate::lock::DataLock;
+use crate::ownership::FileRecord;
+use crate::store::ArtifactHashes;
+use crate::{exec, platform, registry};
+
+/// Version of the state file layout this iii-cli writes.
+///
+/// Adding an optional field does not need a new version: unknown fields are
+/// kept when an older iii-cli saves the file. Bump it, and add a step to
+/// `MIGRATIONS`, when existing fields change meaning or shape.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades from each schema version to the next: `MIGRATIONS[n]` turns a
+/// version `n` document into version `n + 1`.
+const MIGRATIONS: &[fn(&mut serde_json::Map<String, Value>)] = &[migrate_v0_to_v1];
 
 /// Persistent state tracking installed binaries and update checks.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppState {
+    /// Layout version of the file (0 for files written before versioning)
+    #[serde(default)]
+    pub schema_version: u32,
+
     /// Installed binary metadata keyed by binary name
     #[serde(default)]
     pub binaries: HashMap<String, BinaryState>,
@@ -18,15 +39,33 @@ pub struct AppState {
     #[serde(default)]
     pub last_update_check: Option<DateTime<Utc>>,
 
-    /// Hours between update checks (default: 24)
-    #[serde(default = "default_interval")]
-    pub update_check_interval_hours: u64,
+    /// The entry each binary had before its active version last changed,
+    /// restored by `iii-cli rollback`
+    #[serde(default)]
+    pub previous: HashMap<String, BinaryState>,
+
+    /// Fields written by a newer iii-cli, kept as they are
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            binaries: HashMap::new(),
+            last_update_check: None,
+            previous: HashMap::new(),
+            extra: serde_json::Map::new(),
+        }
+    }
 }
 
 /// State for a single installed binary.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BinaryState {
-    /// Installed version
+    /// Active version (the one copied into bin_dir()). Other versions may be
+    /// held side by side in the versioned store.
     pub version: Version,
 
     /// When this version was installed
@@ -34,20 +73,25 @@ pub struct BinaryState {
 
     /// The asset name that was downloaded
     pub asset_name: String,
-}
 
-fn default_interval() -> u64 {
-    24
-}
+    /// Fingerprint of the file iii-cli wrote to bin_dir(), used to detect
+    /// foreign or modified binaries before replacing or removing them
+    #[serde(default)]
+    pub file: Option<FileRecord>,
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            binaries: HashMap::new(),
-            last_update_check: None,
-            update_check_interval_hours: default_interval(),
-        }
-    }
+    /// Hashes of the archive and binary the active version was installed
+    /// from, checked by `iii-cli verify`
+    #[serde(default)]
+    pub hashes: Option<ArtifactHashes>,
+
+    /// Re-detected from bin_dir() when state was rebuilt, so iii-cli cannot
+    /// tell whether it installed the file there
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub recovered: bool,
+
+    /// Fields written by a newer iii-cli, kept as they are
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 impl AppState {
@@ -59,12 +103,139 @@ impl AppState {
         let content = std::fs::read_to_string(path).map_err(|e| {
             StateError::ReadFailed(format!("{}: {}", path.display(), e))
         })?;
-        let state: Self = serde_json::from_str(&content)?;
+        Self::parse(&content)
+    }
+
+    /// Parse state file contents, migrating older layouts to the current one.
+    /// A file from a newer iii-cli is read as is if its layout still fits;
+    /// otherwise it is reported as newer rather than as corrupt, so it is
+    /// never replaced by recovery.
+    fn parse(content: &str) -> Result<Self, StateError> {
+        let mut document: serde_json::Map<String, Value> = serde_json::from_str(content)?;
+        let version = schema_version(&document);
+        if version > SCHEMA_VERSION {
+            return serde_json::from_value(Value::Object(document)).map_err(|_| {
+                StateError::NewerSchema {
+                    found: version,
+                    supported: SCHEMA_VERSION,
+                }
+            });
+        }
+
+        migrate(&mut document);
+        Ok(serde_json::from_value(Value::Object(document))?)
+    }
+
+    /// Refuse to change a state file written by a newer iii-cli, whose
+    /// layout this version may not understand.
+    pub fn check_writable(&self) -> Result<(), StateError> {
+        if self.schema_version > SCHEMA_VERSION {
+            return Err(StateError::NewerSchema {
+                found: self.schema_version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// Load state, recovering from a state file that no longer parses.
+    ///
+    /// The corrupt file is moved to a timestamped backup next to it rather
+    /// than overwritten. Entries that still parse are kept, and binaries in
+    /// bin_dir() without an entry are re-detected by running `--version`.
+    /// Holding the data directory lock keeps other processes from writing
+    /// the file meanwhile.
+    pub fn load_or_recover(path: &Path, _lock: &DataLock) -> Result<Self, StateError> {
+        match Self::load(path) {
+            Err(StateError::ParseFailed(e)) => Self::recover(path, &e.to_string()),
+            other => other,
+        }
+    }
+
+    fn recover(path: &Path, reason: &str) -> Result<Self, StateError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| StateError::ReadFailed(format!("{}: {}", path.display(), e)))?;
+        let backup = backup_path(path, Utc::now());
+        std::fs::rename(path, &backup)?;
+
+        let mut state = Self::salvage(&content);
+        let salvaged = state.binaries.len();
+        let rebuilt = state.rebuild_from_disk();
+        state.save(path)?;
+
+        eprintln!(
+            "  {} {} could not be parsed ({}); moved it to {}",
+            "warning:".yellow(),
+            path.display(),
+            reason,
+            backup.display()
+        );
+        eprintln!(
+            "  {} recovered {} entries, re-detected {} binaries from {}",
+            "note:".cyan(),
+            salvaged,
+            rebuilt.len(),
+            platform::bin_dir().display()
+        );
         Ok(state)
     }
 
+    /// Keep whatever still parses from corrupt state: each binary and
+    /// rollback entry is read on its own, so one bad entry does not lose the
+    /// others. Unknown top-level fields are kept as they are.
+    fn salvage(content: &str) -> Self {
+        let mut state = Self::default();
+        let Ok(Value::Object(mut root)) = serde_json::from_str(content) else {
+            return state;
+        };
+        migrate(&mut root);
+        root.remove("schema_version");
+
+        state.binaries = salvage_entries(root.remove("binaries"));
+        state.previous = salvage_entries(root.remove("previous"));
+        if let Some(last) = root.remove("last_update_check") {
+            state.last_update_check = serde_json::from_value(last).ok().flatten();
+        }
+        state.extra = root;
+        state
+    }
+
+    /// Add entries for managed binaries in bin_dir() that state does not
+    /// know about, asking each for its version. Returns the names added.
+    ///
+    /// Rebuilt entries are marked as recovered: the file may have come from
+    /// somewhere else, so it is not replaced or removed without confirmation
+    /// until iii-cli writes it again.
+    fn rebuild_from_disk(&mut self) -> Vec<String> {
+        let mut rebuilt = Vec::new();
+        for spec in registry::all_binaries() {
+            if self.binaries.contains_key(spec.name) {
+                continue;
+            }
+            let path = platform::binary_path(spec.name);
+            if !path.exists() {
+                continue;
+            }
+            if let Some(version) = exec::probe_version(&path) {
+                self.record_recovered(spec.name, version);
+                rebuilt.push(spec.name.to_string());
+            }
+        }
+        rebuilt
+    }
+
+    /// Record a binary re-detected in bin_dir() as recovered.
+    fn record_recovered(&mut self, binary_name: &str, version: Version) {
+        self.record_install(binary_name, version, platform::asset_name(binary_name));
+        if let Some(binary) = self.binaries.get_mut(binary_name) {
+            binary.recovered = true;
+        }
+    }
+
     /// Save state to the state file using atomic write-to-temp-then-rename.
     pub fn save(&self, path: &Path) -> Result<(), StateError> {
+        self.check_writable()?;
+
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             if !parent.exists() {
@@ -79,38 +250,94 @@ impl AppState {
         std::fs::write(&temp_path, &content)?;
 
         // Atomic rename
-        std::fs::rename(&temp_path, path).map_err(|e| {
+        std::fs::rename(&temp_path, path).inspect_err(|_| {
             // Clean up temp file on failure
             let _ = std::fs::remove_file(&temp_path);
-            e
         })?;
 
         Ok(())
     }
 
-    /// Check if an update check is due based on the configured interval.
-    pub fn is_update_check_due(&self) -> bool {
+    /// Check if an update check is due, given the hours between checks
+    /// (`update.check_interval_hours` in config).
+    pub fn is_update_check_due(&self, interval_hours: u64) -> bool {
         match self.last_update_check {
             None => true,
             Some(last) => {
                 let elapsed = Utc::now() - last;
-                elapsed.num_hours() >= self.update_check_interval_hours as i64
+                elapsed.num_hours() >= interval_hours as i64
             }
         }
     }
 
     /// Record a binary installation.
     pub fn record_install(&mut self, binary_name: &str, version: Version, asset_name: String) {
+        self.remember_previous(binary_name, &version);
         self.binaries.insert(
             binary_name.to_string(),
             BinaryState {
                 version,
                 installed_at: Utc::now(),
                 asset_name,
+                file: None,
+                hashes: None,
+                recovered: false,
+                extra: serde_json::Map::new(),
             },
         );
     }
 
+    /// Switch a binary's active version to one already held in the versioned store.
+    pub fn set_active(&mut self, binary_name: &str, version: Version, asset_name: String) {
+        self.remember_previous(binary_name, &version);
+        match self.binaries.get_mut(binary_name) {
+            Some(binary) => binary.version = version,
+            None => self.record_install(binary_name, version, asset_name),
+        }
+    }
+
+    /// Record the fingerprint of the file written to bin_dir() for a binary.
+    pub fn set_file_record(&mut self, binary_name: &str, record: Option<FileRecord>) {
+        if let Some(binary) = self.binaries.get_mut(binary_name) {
+            binary.recovered = false;
+            binary.file = record;
+        }
+    }
+
+    /// Record the hashes of the active version of a binary.
+    pub fn set_hashes(&mut self, binary_name: &str, hashes: Option<ArtifactHashes>) {
+        if let Some(binary) = self.binaries.get_mut(binary_name) {
+            binary.hashes = hashes;
+        }
+    }
+
+    /// Keep the current entry of a binary as its rollback target before it
+    /// switches to another version.
+    fn remember_previous(&mut self, binary_name: &str, version: &Version) {
+        if let Some(current) = self.binaries.get(binary_name) {
+            if &current.version != version {
+                self.previous.insert(binary_name.to_string(), current.clone());
+            }
+        }
+    }
+
+    /// Swap a binary's entry with the one it had before its last version
+    /// change, so rolling back twice returns to where it started. Returns the
+    /// restored entry, or None if there is nothing to roll back to.
+    pub fn rollback(&mut self, binary_name: &str) -> Option<&BinaryState> {
+        let previous = self.previous.remove(binary_name)?;
+        if let Some(current) = self.binaries.insert(binary_name.to_string(), previous) {
+            self.previous.insert(binary_name.to_string(), current);
+        }
+        self.binaries.get(binary_name)
+    }
+
+    /// Forget a binary, returning its previous state entry if there was one.
+    pub fn remove_binary(&mut self, binary_name: &str) -> Option<BinaryState> {
+        self.previous.remove(binary_name);
+        self.binaries.remove(binary_name)
+    }
+
     /// Get the installed version of a binary, if any.
     pub fn installed_version(&self, binary_name: &str) -> Option<&Version> {
         self.binaries.get(binary_name).map(|b| &b.version)
@@ -122,9 +349,51 @@ impl AppState {
     }
 }
 
+/// The entries of a map of binary entries that still parse.
+fn salvage_entries(map: Option<Value>) -> HashMap<String, BinaryState> {
+    let Some(Value::Object(map)) = map else {
+        return HashMap::new();
+    };
+    map.into_iter()
+        .filter_map(|(name, entry)| Some((name, serde_json::from_value(entry).ok()?)))
+        .collect()
+}
+
+/// Schema version of a state document (0 for files written before versioning).
+fn schema_version(document: &serde_json::Map<String, Value>) -> u32 {
+    document
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32
+}
+
+/// Upgrade a state document from its schema version to the current one.
+fn migrate(document: &mut serde_json::Map<String, Value>) {
+    let version = schema_version(document);
+    for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migrate(document);
+        document.insert("schema_version".to_string(), Value::from(from as u32 + 1));
+    }
+}
+
+/// Version 0 files carry `update_check_interval_hours`, now the
+/// `update.check_interval_hours` config setting.
+fn migrate_v0_to_v1(document: &mut serde_json::Map<String, Value>) {
+    document.remove("update_check_interval_hours");
+}
+
+/// Where a corrupt state file is moved, e.g. `state.json.corrupt-20261016T101500Z`.
+fn backup_path(path: &Path, now: DateTime<Utc>) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now.format("%Y%m%dT%H%M%SZ")));
+    path.with_file_name(name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ownership::{self, Ownership};
+    use std::io::IsTerminal;
     use tempfile::NamedTempFile;
 
     #[test]
@@ -132,7 +401,6 @@ mod tests {
         let state = AppState::default();
         assert!(state.binaries.is_empty());
         assert!(state.last_update_check.is_none());
-        assert_eq!(state.update_check_interval_hours, 24);
     }
 
     #[test]
@@ -171,13 +439,66 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_set_active_keeps_install_metadata() {
+        let mut state = AppState::default();
+        state.record_install("iii", Version::new(0, 4, 2), "iii.tar.gz".to_string());
+        let installed_at = state.binaries["iii"].installed_at;
+
+        state.set_active("iii", Version::new(0, 4, 1), "other.tar.gz".to_string());
+
+        let binary = &state.binaries["iii"];
+        assert_eq!(binary.version, Version::new(0, 4, 1));
+        assert_eq!(binary.installed_at, installed_at);
+        assert_eq!(binary.asset_name, "iii.tar.gz");
+
+        state.set_active("iii-console", Version::new(0, 3, 1), "console.tar.gz".to_string());
+        assert_eq!(state.installed_version("iii-console"), Some(&Version::new(0, 3, 1)));
+    }
+
+    #[test]
+    fn test_recovered_binary_is_not_replaced_without_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"installed by brew").unwrap();
+
+        let mut state = AppState::default();
+        state.record_recovered("iii", Version::new(0, 4, 2));
+        let entry = state.binaries.get("iii");
+        assert_eq!(ownership::check(&path, entry), Ownership::Unverified);
+        if !std::io::stdin().is_terminal() {
+            assert!(ownership::ensure_may_modify(&path, entry, "replace", false).is_err());
+        }
+
+        // Survives a reload until iii-cli writes the file itself
+        let saved = serde_json::to_string(&state).unwrap();
+        let mut state: AppState = serde_json::from_str(&saved).unwrap();
+        assert!(state.binaries["iii"].recovered);
+
+        state.set_file_record("iii", Some(FileRecord::capture(&path).unwrap()));
+        let entry = state.binaries.get("iii");
+        assert_eq!(ownership::check(&path, entry), Ownership::Owned);
+    }
+
+    #[test]
+    fn test_remove_binary() {
+        let mut state = AppState::default();
+        state.record_install("iii", Version::new(0, 4, 2), "iii.tar.gz".to_string());
+
+        let removed = state.remove_binary("iii").unwrap();
+        assert_eq!(removed.version, Version::new(0, 4, 2));
+        assert!(state.installed_version("iii").is_none());
+        assert!(state.remove_binary("iii").is_none());
+    }
+
     #[test]
     fn test_is_update_check_due() {
         let mut state = AppState::default();
-        assert!(state.is_update_check_due());
+        assert!(state.is_update_check_due(24));
 
         state.mark_update_checked();
-        assert!(!state.is_update_check_due());
+        assert!(!state.is_update_check_due(24));
+        assert!(state.is_update_check_due(0));
     }
 
     #[test]
@@ -192,4 +513,133 @@ mod tests {
         let temp_path = path.with_extension("json.tmp");
         assert!(!temp_path.exists());
     }
+
+    #[test]
+    fn test_salvage_keeps_entries_that_parse() {
+        let content = r#"{
+            "binaries": {
+                "iii": {"version": "0.4.2", "installed_at": "2026-01-01T00:00:00Z", "asset_name": "iii.tar.gz"},
+                "iii-console": {"version": "not-a-version", "installed_at": "2026-01-01T00:00:00Z", "asset_name": "x"}
+            },
+            "previous": {
+                "iii": {"version": "0.4.1", "installed_at": "2026-01-01T00:00:00Z", "asset_name": "iii.tar.gz"},
+                "iii-console": {"version": "0.3.0"}
+            },
+            "last_update_check": "2026-01-02T00:00:00Z"
+        }"#;
+        let state = AppState::salvage(content);
+        assert_eq!(state.installed_version("iii"), Some(&Version::new(0, 4, 2)));
+        assert!(state.installed_version("iii-console").is_none());
+        assert_eq!(state.previous["iii"].version, Version::new(0, 4, 1));
+        assert!(!state.previous.contains_key("iii-console"));
+        assert!(state.last_update_check.is_some());
+
+        assert!(AppState::salvage("{\"binaries\": {\"iii\": ").binaries.is_empty());
+    }
+
+    #[test]
+    fn test_backup_path() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T10:15:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            backup_path(Path::new("/data/state.json"), now),
+            PathBuf::from("/data/state.json.corrupt-20261016T101500Z")
+        );
+    }
+
+    #[test]
+    fn test_migrates_unversioned_state() {
+        let state = AppState::parse(
+            r#"{"binaries": {}, "last_update_check": null, "update_check_interval_hours": 24}"#,
+        )
+        .unwrap();
+        assert_eq!(state.schema_version, SCHEMA_VERSION);
+        assert!(state.extra.is_empty());
+        assert!(state.check_writable().is_ok());
+    }
+
+    #[test]
+    fn test_unknown_fields_survive_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "schema_version": 1,
+                "binaries": {
+                    "iii": {"version": "0.4.2", "installed_at": "2026-01-01T00:00:00Z", "asset_name": "iii.tar.gz", "channel": "beta"}
+                },
+                "telemetry": {"enabled": false}
+            }"#,
+        )
+        .unwrap();
+
+        let mut state = AppState::load(&path).unwrap();
+        state.mark_update_checked();
+        state.save(&path).unwrap();
+
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["telemetry"]["enabled"], Value::Bool(false));
+        assert_eq!(saved["binaries"]["iii"]["channel"], Value::from("beta"));
+    }
+
+    #[test]
+    fn test_newer_schema_is_not_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let content = format!(r#"{{"schema_version": {}, "binaries": {{}}}}"#, SCHEMA_VERSION + 1);
+        std::fs::write(&path, &content).unwrap();
+
+        let state = AppState::load(&path).unwrap();
+        assert!(matches!(state.save(&path), Err(StateError::NewerSchema { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn test_newer_schema_with_changed_fields_is_not_recovered() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let content = format!(
+            r#"{{"schema_version": {}, "binaries": [{{"name": "iii"}}]}}"#,
+            SCHEMA_VERSION + 1
+        );
+        std::fs::write(&path, &content).unwrap();
+
+        let lock = crate::lock::acquire(&dir.path().join("lock"), std::time::Duration::ZERO).unwrap();
+        assert!(matches!(
+            AppState::load_or_recover(&path, &lock),
+            Err(StateError::NewerSchema { .. })
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_salvage_keeps_unknown_fields() {
+        let state = AppState::salvage(
+            r#"{"schema_version": 1, "binaries": {"iii": 3}, "telemetry": {"enabled": false}}"#,
+        );
+        assert_eq!(state.schema_version, SCHEMA_VERSION);
+        assert_eq!(state.extra["telemetry"]["enabled"], Value::Bool(false));
+        assert!(!state.extra.contains_key("binaries"));
+    }
+
+    #[test]
+    fn test_rollback_swaps_with_previous_entry() {
+        let mut state = AppState::default();
+        assert!(state.rollback("iii").is_none());
+
+        state.record_install("iii", Version::new(0, 4, 1), "iii.tar.gz".to_string());
+        state.record_install("iii", Version::new(0, 4, 1), "iii.tar.gz".to_string());
+        assert!(state.previous.is_empty());
+
+        state.record_install("iii", Version::new(0, 4, 2), "iii.tar.gz".to_string());
+        assert_eq!(state.rollback("iii").unwrap().version, Version::new(0, 4, 1));
+        assert_eq!(state.previous["iii"].version, Version::new(0, 4, 2));
+        assert_eq!(state.rollback("iii").unwrap().version, Version::new(0, 4, 2));
+
+        state.remove_binary("iii");
+        assert!(state.rollback("iii").is_none());
+    }
 }
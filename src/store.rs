@@ -0,0 +1,249 @@
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+use crate::ownership::{self, FileRecord, Ownership};
+use crate::platform;
+use crate::state::BinaryState;
+
+/// File next to each stored binary recording the hashes it was installed with.
+const HASHES_FILE: &str = "hashes.json";
+
+/// SHA256 of a downloaded archive and of the binary extracted from it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactHashes {
+    pub archive_sha256: String,
+    pub binary_sha256: String,
+}
+
+/// List the versions of a binary held in the versioned store, oldest first.
+pub fn stored_versions(binary_name: &str) -> Vec<Version> {
+    versions_in(&platform::versions_dir().join(binary_name), binary_name)
+}
+
+/// Check whether a specific version of a binary is held in the versioned store.
+pub fn is_stored(binary_name: &str, version: &Version) -> bool {
+    platform::versioned_binary_path(binary_name, version).exists()
+}
+
+/// Find the highest stored version of a binary that satisfies `matches`.
+pub fn best_stored_match(binary_name: &str, matches: impl Fn(&Version) -> bool) -> Option<Version> {
+    stored_versions(binary_name)
+        .into_iter()
+        .filter(|v| matches(v))
+        .max()
+}
+
+fn hashes_path(binary_name: &str, version: &Version) -> PathBuf {
+    platform::versioned_binary_path(binary_name, version).with_file_name(HASHES_FILE)
+}
+
+/// Contents of a stored version's hashes file.
+#[derive(Debug, Serialize, Deserialize)]
+struct HashesFile {
+    #[serde(flatten)]
+    hashes: ArtifactHashes,
+    /// Fingerprint of the stored binary, used to skip re-hashing it before
+    /// each run while it is unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<FileRecord>,
+}
+
+/// Record the hashes a stored version was installed with, and fingerprint
+/// the stored binary.
+pub fn record_hashes(
+    binary_name: &str,
+    version: &Version,
+    hashes: &ArtifactHashes,
+) -> Result<(), StorageError> {
+    let path = hashes_path(binary_name, version);
+    let file = FileRecord::capture(&platform::versioned_binary_path(binary_name, version)).ok();
+    let content = serde_json::to_vec_pretty(&HashesFile {
+        hashes: hashes.clone(),
+        file,
+    })
+    .map_err(std::io::Error::other)?;
+    std::fs::write(&path, content).map_err(|e| StorageError::WriteFile {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+/// The hashes recorded for a stored version, if any.
+pub fn recorded_hashes(binary_name: &str, version: &Version) -> Option<ArtifactHashes> {
+    let content = std::fs::read(hashes_path(binary_name, version)).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Fingerprint of a stored binary as it was installed. Versions stored before
+/// fingerprints were recorded only have the binary's hash, so they are
+/// re-hashed whenever they are checked.
+pub fn recorded_file(binary_name: &str, version: &Version) -> Option<FileRecord> {
+    let content = std::fs::read(hashes_path(binary_name, version)).ok()?;
+    let recorded: HashesFile = serde_json::from_slice(&content).ok()?;
+    Some(
+        recorded
+            .file
+            .unwrap_or_else(|| FileRecord::hash_only(recorded.hashes.binary_sha256)),
+    )
+}
+
+/// Whether a stored version was installed from the archive with this SHA256.
+/// False when no hashes were recorded for it.
+pub fn has_archive(binary_name: &str, version: &Version, archive_sha256: &str) -> bool {
+    is_stored(binary_name, version)
+        && recorded_hashes(binary_name, version)
+            .is_some_and(|h| h.archive_sha256.eq_ignore_ascii_case(archive_sha256))
+}
+
+/// Make a stored version the active one by atomically replacing the binary
+/// in bin_dir() with a copy from the store. No download is performed.
+pub fn activate(binary_name: &str, version: &Version) -> Result<(), StorageError> {
+    let source = platform::versioned_binary_path(binary_name, version);
+    if !source.exists() {
+        return Err(StorageError::VersionNotInstalled {
+            binary: binary_name.to_string(),
+            version: version.to_string(),
+        });
+    }
+    copy_atomic(&source, &platform::binary_path(binary_name))
+}
+
+/// Copy the currently active binary into the store under the version its
+/// state entry records, so binaries installed before the versioned store
+/// existed can still be switched back to. Does nothing if the version is
+/// already stored, or if the active binary is foreign or was modified, since
+/// it would not be the recorded version.
+pub fn adopt_active(binary_name: &str, entry: &BinaryState) -> Result<(), StorageError> {
+    let source = platform::binary_path(binary_name);
+    let target = platform::versioned_binary_path(binary_name, &entry.version);
+    let ownership = ownership::check(&source, Some(entry));
+    if target.exists() || !matches!(ownership, Ownership::Owned | Ownership::Untracked) {
+        return Ok(());
+    }
+    copy_atomic(&source, &target)
+}
+
+/// Remove every stored version of a binary. Returns the versions removed.
+pub fn remove_all(binary_name: &str) -> Result<Vec<Version>, StorageError> {
+    let dir = platform::versions_dir().join(binary_name);
+    let removed = versions_in(&dir, binary_name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    Ok(removed)
+}
+
+/// Scan a binary's store directory for version subdirectories that contain the binary.
+fn versions_in(dir: &Path, binary_name: &str) -> Vec<Version> {
+    let exe_name = platform::exe_name(binary_name);
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut versions: Vec<Version> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join(&exe_name).exists())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| Version::parse(name).ok())
+        })
+        .collect();
+    versions.sort();
+    versions
+}
+
+/// Copy a file to the target path via a temp file in the target directory,
+/// then rename, so the target is never observed half-written.
+fn copy_atomic(source: &Path, target: &Path) -> Result<(), StorageError> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| StorageError::CreateDir {
+            path: parent.display().to_string(),
+            source: e,
+        })?;
+    }
+
+    let temp_path = target.with_extension("tmp");
+
+    // fs::copy carries over permissions, including the executable bit
+    std::fs::copy(source, &temp_path).map_err(|e| StorageError::WriteFile {
+        path: temp_path.display().to_string(),
+        source: e,
+    })?;
+
+    std::fs::rename(&temp_path, target).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_in_sorted_and_filtered() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = platform::exe_name("iii");
+        for version in ["0.4.1", "0.10.0", "0.4.0"] {
+            let version_dir = dir.path().join(version);
+            std::fs::create_dir_all(&version_dir).unwrap();
+            std::fs::write(version_dir.join(&exe), b"bin").unwrap();
+        }
+        // Empty version dir (interrupted install) and non-version entries are ignored
+        std::fs::create_dir_all(dir.path().join("0.5.0")).unwrap();
+        std::fs::create_dir_all(dir.path().join("not-a-version")).unwrap();
+
+        let versions = versions_in(dir.path(), "iii");
+        assert_eq!(
+            versions,
+            vec![
+                Version::new(0, 4, 0),
+                Version::new(0, 4, 1),
+                Version::new(0, 10, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_versions_in_missing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(versions_in(&dir.path().join("missing"), "iii").is_empty());
+    }
+
+    #[test]
+    fn test_copy_atomic_replaces_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("store").join("iii");
+        let target = dir.path().join("bin").join("iii");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        std::fs::write(&source, b"new").unwrap();
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::write(&target, b"old").unwrap();
+
+        copy_atomic(&source, &target).unwrap();
+
+        assert_eq!(std::fs::read(&target).unwrap(), b"new");
+        assert!(!target.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn test_hashes_file_without_fingerprint() {
+        let recorded: HashesFile =
+            serde_json::from_str(r#"{"archive_sha256": "aa", "binary_sha256": "bb"}"#).unwrap();
+        assert_eq!(recorded.hashes.binary_sha256, "bb");
+        assert!(recorded.file.is_none());
+
+        let written = serde_json::to_value(&recorded).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"archive_sha256": "aa", "binary_sha256": "bb"})
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+
+use crate::error::ToolchainError;
+use crate::registry;
+
+/// File name of the project-level toolchain file, searched upward from the cwd.
+pub const TOOLCHAIN_FILE: &str = "iii-toolchain.toml";
+
+/// Raw contents of an iii-toolchain.toml file.
+///
+/// ```toml
+/// [tools]
+/// iii = "0.4.2"          # exact version
+/// iii-console = "^0.3"   # semver range
+/// ```
+#[derive(Debug, Default, Deserialize)]
+struct ToolchainFile {
+    #[serde(default)]
+    tools: BTreeMap<String, String>,
+}
+
+/// A version pin for a single binary.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionPin {
+    /// A bare version such as "0.4.2" pins exactly that release
+    Exact(Version),
+    /// Anything else is parsed as a semver range (e.g., "^0.3", ">=0.4, <0.6")
+    Range(VersionReq),
+}
+
+impl VersionPin {
+    /// Parse a pin from its toolchain file representation.
+    pub fn parse(value: &str) -> Result<Self, semver::Error> {
+        let cleaned = value.trim();
+        let cleaned = cleaned.strip_prefix('v').unwrap_or(cleaned);
+        match Version::parse(cleaned) {
+            Ok(version) => Ok(Self::Exact(version)),
+            Err(_) => VersionReq::parse(value.trim()).map(Self::Range),
+        }
+    }
+
+    /// Check whether a version satisfies this pin.
+    pub fn matches(&self, version: &Version) -> bool {
+        match self {
+            Self::Exact(pinned) => pinned == version,
+            Self::Range(req) => req.matches(version),
+        }
+    }
+}
+
+impl std::fmt::Display for VersionPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(version) => write!(f, "{}", version),
+            Self::Range(req) => write!(f, "{}", req),
+        }
+    }
+}
+
+/// A parsed toolchain file with pins keyed by binary name.
+#[derive(Debug)]
+pub struct Toolchain {
+    /// Path of the file the pins were read from
+    pub path: PathBuf,
+    /// Version pins keyed by binary name (e.g., "iii", "iii-console")
+    pub pins: BTreeMap<String, VersionPin>,
+}
+
+impl Toolchain {
+    /// Find and load the nearest toolchain file, searching upward from `start`.
+    /// Returns Ok(None) if no toolchain file exists.
+    pub fn discover(start: &Path) -> Result<Option<Self>, ToolchainError> {
+        match find_toolchain_file(start) {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Load a toolchain file from disk.
+    pub fn load(path: &Path) -> Result<Self, ToolchainError> {
+        let content = std::fs::read_to_string(path).map_err(|e| ToolchainError::Read {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        Self::parse(path, &content)
+    }
+
+    /// Parse toolchain file contents. Tools may be named by binary ("iii")
+    /// or by command ("start"); pins are normalized to binary names.
+    pub fn parse(path: &Path, content: &str) -> Result<Self, ToolchainError> {
+        let file: ToolchainFile = toml::from_str(content).map_err(|e| ToolchainError::Parse {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+
+        let mut pins = BTreeMap::new();
+        for (tool, value) in file.tools {
+            let spec = registry::resolve_binary_for_update(&tool).map_err(|_| {
+                ToolchainError::UnknownTool {
+                    path: path.display().to_string(),
+                    tool: tool.clone(),
+                    known: registry::all_binaries()
+                        .iter()
+                        .map(|s| s.name)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                }
+            })?;
+            let pin = VersionPin::parse(&value).map_err(|e| ToolchainError::InvalidPin {
+                tool: tool.clone(),
+                value: value.clone(),
+                reason: e.to_string(),
+            })?;
+            pins.insert(spec.name.to_string(), pin);
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            pins,
+        })
+    }
+
+    /// Get the pin for a binary, if the toolchain file declares one.
+    pub fn pin_for(&self, binary_name: &str) -> Option<&VersionPin> {
+        self.pins.get(binary_name)
+    }
+}
+
+/// Search `start` and its ancestors for a toolchain file.
+pub fn find_toolchain_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(TOOLCHAIN_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exact_and_range_pins() {
+        let toolchain = Toolchain::parse(
+            Path::new(TOOLCHAIN_FILE),
+            r#"
+            [tools]
+            iii = "0.4.2"
+            iii-console = "^0.3"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            toolchain.pin_for("iii"),
+            Some(&VersionPin::Exact(Version::new(0, 4, 2)))
+        );
+        let console = toolchain.pin_for("iii-console").unwrap();
+        assert!(console.matches(&Version::new(0, 3, 9)));
+        assert!(!console.matches(&Version::new(0, 4, 0)));
+        assert!(toolchain.pin_for("iii-tools").is_none());
+    }
+
+    #[test]
+    fn test_exact_pin_does_not_match_newer_patch() {
+        let pin = VersionPin::parse("v0.4.2").unwrap();
+        assert!(pin.matches(&Version::new(0, 4, 2)));
+        assert!(!pin.matches(&Version::new(0, 4, 3)));
+    }
+
+    #[test]
+    fn test_parse_normalizes_command_names() {
+        let toolchain = Toolchain::parse(
+            Path::new(TOOLCHAIN_FILE),
+            "[tools]\nstart = \"0.4.2\"\nmotia = \"1\"\n",
+        )
+        .unwrap();
+        assert!(toolchain.pin_for("iii").is_some());
+        assert!(toolchain.pin_for("motia-cli").is_some());
+    }
+
+    #[test]
+    fn test_parse_unknown_tool() {
+        let result = Toolchain::parse(Path::new(TOOLCHAIN_FILE), "[tools]\nfoobar = \"1.0.0\"\n");
+        assert!(matches!(result, Err(ToolchainError::UnknownTool { .. })));
+    }
+
+    #[test]
+    fn test_parse_invalid_pin() {
+        let result = Toolchain::parse(Path::new(TOOLCHAIN_FILE), "[tools]\niii = \"latest\"\n");
+        assert!(matches!(result, Err(ToolchainError::InvalidPin { .. })));
+    }
+
+    #[test]
+    fn test_find_toolchain_file_searches_upward() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("services").join("api");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.path().join(TOOLCHAIN_FILE), "[tools]\n").unwrap();
+
+        let found = find_toolchain_file(&nested).unwrap();
+        assert_eq!(found, dir.path().join(TOOLCHAIN_FILE));
+    }
+}
@@ -1,13 +1,20 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use colored::Colorize;
 use semver::Version;
 
-use crate::error::RegistryError;
-use crate::github::{self, IiiGithubError};
+use crate::error::{DownloadError, LockError, PolicyError, RegistryError, StorageError};
+use crate::github::{self, IiiGithubError, Release, ReleaseAsset};
+use crate::lockfile::{LockedArtifact, LockedTool};
 use crate::registry::{self, BinarySpec};
 use crate::state::AppState;
-use crate::{download, platform};
+use crate::toolchain::VersionPin;
+use crate::download::{self, Checksum, ChecksumStatus};
+use crate::history::{self, Action};
+use crate::ownership::{self, FileRecord};
+use crate::store::{self, ArtifactHashes};
+use crate::verify::{self, Integrity};
+use crate::{platform, policy, remote_registry};
 
 /// Information about an available update.
 #[derive(Debug)]
@@ -74,22 +81,12 @@ pub fn print_update_notifications(updates: &[UpdateInfo]) {
             update.current_version.to_string().dimmed(),
             update.latest_version.to_string().green(),
             // Use the CLI command name, not the binary name
-            cli_command_for_binary(&update.binary_name).unwrap_or(&update.binary_name),
+            registry::primary_command(&update.binary_name).unwrap_or(&update.binary_name),
         );
     }
     eprintln!();
 }
 
-/// Get the CLI command name for a binary name.
-fn cli_command_for_binary(binary_name: &str) -> Option<&str> {
-    for spec in registry::REGISTRY {
-        if spec.name == binary_name {
-            return spec.commands.first().map(|c| c.cli_command);
-        }
-    }
-    None
-}
-
 /// Run the background update check with a bounded timeout.
 /// Compatible with the process-replacement lifecycle.
 ///
@@ -97,9 +94,10 @@ fn cli_command_for_binary(binary_name: &str) -> Option<&str> {
 /// or None if it times out (will retry on next invocation).
 pub async fn run_background_check(
     state: &AppState,
-    timeout_ms: u64,
+    interval_hours: u64,
+    timeout: Duration,
 ) -> Option<(Vec<UpdateInfo>, bool)> {
-    if !state.is_update_check_due() {
+    if !state.is_update_check_due(interval_hours) {
         return None;
     }
 
@@ -110,13 +108,15 @@ pub async fn run_background_check(
 
     let check = async {
         let updates = check_for_updates(&client, state).await;
+        // New tools in the registry manifest become available on the next run
+        let _ = remote_registry::refresh(&client).await;
         (updates, true) // true = check completed, should update timestamp
     };
 
-    match tokio::time::timeout(Duration::from_millis(timeout_ms), check).await {
-        Ok(result) => Some(result),
-        Err(_) => None, // Timed out, will retry next run
-    }
+    // Timed out returns None, will retry next run
+    tokio::time::timeout(timeout, check)
+        .await
+        .ok()
 }
 
 /// Check if a managed binary is installed on disk.
@@ -126,22 +126,27 @@ fn is_binary_installed(name: &str) -> bool {
 }
 
 /// Update a specific binary to the latest version.
+///
+/// `force` allows replacing a binary in bin_dir() that iii-cli did not write
+/// or that was modified since.
 pub async fn update_binary(
     client: &reqwest::Client,
     spec: &BinarySpec,
     state: &mut AppState,
+    force: bool,
 ) -> Result<UpdateResult, UpdateError> {
-    // Check platform support
+    let started = Instant::now();
+
+    // Check platform support and policy
     platform::check_platform_support(spec)?;
+    policy::current().check_repo(spec)?;
 
     let binary_installed = is_binary_installed(spec.name);
 
     eprintln!("  Checking for updates to {}...", spec.name);
 
-    // Fetch latest release
-    let release = github::fetch_latest_release(client, spec).await?;
-    let latest_version = github::parse_release_version(&release.tag_name)
-        .map_err(|e| UpdateError::VersionParse(e.to_string()))?;
+    // Fetch latest release the policy allows
+    let (release, latest_version) = fetch_latest_allowed_release(client, spec).await?;
 
     // Check if already up to date (only if the binary file actually exists on disk)
     if binary_installed {
@@ -155,26 +160,6 @@ pub async fn update_binary(
         }
     }
 
-    // Find asset for current platform
-    let asset_name = platform::asset_name(spec.name);
-    let asset = github::find_asset(&release, &asset_name).ok_or_else(|| {
-        UpdateError::Github(IiiGithubError::Network(
-            crate::error::NetworkError::AssetNotFound {
-                binary: spec.name.to_string(),
-                platform: platform::current_target().to_string(),
-            },
-        ))
-    })?;
-
-    // Find checksum asset in release (separate asset, not appended URL)
-    let checksum_url = if spec.has_checksum {
-        let checksum_name = platform::checksum_asset_name(spec.name);
-        github::find_asset(&release, &checksum_name)
-            .map(|a| a.browser_download_url.clone())
-    } else {
-        None
-    };
-
     // Capture previous version before record_install overwrites it.
     // Only consider state if the binary actually exists on disk —
     // stale state entries for missing binaries should show as fresh installs.
@@ -198,41 +183,497 @@ pub async fn update_binary(
         );
     }
 
-    // Download and install
-    let target_path = platform::binary_path(spec.name);
-    download::download_and_install(
+    let checksum = install_release(client, spec, &release, &latest_version, state, force).await?;
+    history::record(
+        history::Entry::new(Action::for_change(previous_version.as_ref()), spec.name, started)
+            .versions(previous_version.clone(), Some(latest_version.clone()))
+            .download(platform::asset_name(spec.name), checksum),
+    );
+
+    Ok(UpdateResult::Updated {
+        binary: spec.name.to_string(),
+        from: previous_version,
+        to: latest_version,
+    })
+}
+
+/// Install a specific version of a binary, replacing whichever version is
+/// currently installed (newer or older).
+pub async fn install_version(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    version: &Version,
+    state: &mut AppState,
+    force: bool,
+) -> Result<UpdateResult, UpdateError> {
+    let started = Instant::now();
+    platform::check_platform_support(spec)?;
+
+    let binary_installed = is_binary_installed(spec.name);
+    let previous_version = if binary_installed {
+        state.installed_version(spec.name).cloned()
+    } else {
+        None
+    };
+
+    if previous_version.as_ref() == Some(version) {
+        return Ok(UpdateResult::AlreadyInstalled {
+            binary: spec.name.to_string(),
+            version: version.clone(),
+        });
+    }
+
+    // Switch to a version already held in the store without downloading
+    if store::is_stored(spec.name, version) {
+        eprintln!("  Switching {} to stored v{}...", spec.name, version);
+        activate_version(spec, version, state, force)?;
+        history::record(
+            history::Entry::new(Action::for_change(previous_version.as_ref()), spec.name, started)
+                .versions(previous_version.clone(), Some(version.clone())),
+        );
+        return Ok(UpdateResult::Updated {
+            binary: spec.name.to_string(),
+            from: previous_version,
+            to: version.clone(),
+        });
+    }
+
+    eprintln!("  Resolving {} v{}...", spec.name, version);
+    let release = github::fetch_release_by_version(client, spec, version).await?;
+
+    eprintln!("  Installing {} v{}...", spec.name, version);
+    let checksum = install_release(client, spec, &release, version, state, force).await?;
+    history::record(
+        history::Entry::new(Action::for_change(previous_version.as_ref()), spec.name, started)
+            .versions(previous_version.clone(), Some(version.clone()))
+            .download(platform::asset_name(spec.name), checksum),
+    );
+
+    Ok(UpdateResult::Updated {
+        binary: spec.name.to_string(),
+        from: previous_version,
+        to: version.clone(),
+    })
+}
+
+/// Make sure a version satisfying a toolchain pin is held in the versioned
+/// store, downloading the best matching release if none is stored yet.
+///
+/// Returns the version to run. The active version in bin_dir() and the
+/// state file are left untouched, so pins only affect their own project.
+pub async fn ensure_pinned_version(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    pin: &VersionPin,
+) -> Result<Version, UpdateError> {
+    platform::check_platform_support(spec)?;
+
+    let policy = policy::current();
+    if let Some(version) = store::best_stored_match(spec.name, |v| {
+        pin.matches(v) && !policy.is_forbidden(spec.name, v)
+    }) {
+        return Ok(version);
+    }
+
+    let (version, release) = resolve_pinned_release(client, spec, pin).await?;
+    eprintln!("  Installing {} v{} (pinned {})...", spec.name, version, pin);
+    store_release(client, spec, &release, &version, None).await?;
+    Ok(version)
+}
+
+/// Find the release that best satisfies a pin: the exact tag for exact pins,
+/// otherwise the highest stable release matching the range.
+pub async fn resolve_pinned_release(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    pin: &VersionPin,
+) -> Result<(Version, Release), UpdateError> {
+    match pin {
+        VersionPin::Exact(version) => {
+            let release = github::fetch_release_by_version(client, spec, version).await?;
+            Ok((version.clone(), release))
+        }
+        VersionPin::Range(req) => {
+            let releases = github::fetch_releases(client, spec).await?;
+            let policy = policy::current();
+            let (version, release) = github::find_matching_release(&releases, |v| {
+                req.matches(v) && !policy.is_forbidden(spec.name, v)
+            })
+                .ok_or_else(|| RegistryError::NoMatchingRelease {
+                    binary: spec.name.to_string(),
+                    requirement: req.to_string(),
+                })?;
+            Ok((version, release.clone()))
+        }
+    }
+}
+
+/// Install the exact archive recorded in a lockfile into the versioned store.
+/// Fails if the downloaded archive's SHA256 differs from the locked hash.
+/// A stored copy installed from a different archive is replaced.
+pub async fn install_locked(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    tool: &LockedTool,
+) -> Result<UpdateResult, UpdateError> {
+    platform::check_platform_support(spec)?;
+    policy::current().check_version(spec, &tool.version)?;
+
+    let artifact = tool
+        .artifact_for(platform::current_target())
+        .ok_or_else(|| {
+            UpdateError::Github(IiiGithubError::Network(
+                crate::error::NetworkError::AssetNotFound {
+                    binary: spec.name.to_string(),
+                    platform: platform::current_target().to_string(),
+                },
+            ))
+        })?;
+
+    if store::has_archive(spec.name, &tool.version, &artifact.sha256) {
+        return Ok(UpdateResult::AlreadyInstalled {
+            binary: spec.name.to_string(),
+            version: tool.version.clone(),
+        });
+    }
+    if store::is_stored(spec.name, &tool.version) {
+        eprintln!(
+            "  {} Stored {} v{} was not installed from the locked archive, replacing it",
+            "warning:".yellow(),
+            spec.name,
+            tool.version
+        );
+    }
+
+    let asset = locked_asset(spec, tool, artifact)?;
+
+    eprintln!("  Installing {} v{} (locked)...", spec.name, tool.version);
+    let target_path = platform::versioned_binary_path(spec.name, &tool.version);
+    let hashes = download::download_and_install(
         client,
         spec,
-        asset,
-        checksum_url.as_deref(),
+        &asset,
+        &tool.tag,
+        Checksum::Locked(&artifact.sha256),
         &target_path,
     )
     .await?;
-
-    // Update state
-    state.record_install(spec.name, latest_version.clone(), asset_name);
+    store::record_hashes(spec.name, &tool.version, &hashes)?;
 
     Ok(UpdateResult::Updated {
         binary: spec.name.to_string(),
-        from: previous_version,
-        to: latest_version,
+        from: None,
+        to: tool.version.clone(),
+    })
+}
+
+/// Whether the store holds a locked tool, installed from the locked archive
+/// for this platform.
+pub fn is_locked_installed(spec: &BinarySpec, tool: &LockedTool) -> bool {
+    tool.artifact_for(platform::current_target())
+        .is_some_and(|artifact| store::has_archive(spec.name, &tool.version, &artifact.sha256))
+}
+
+/// The release asset a lockfile entry refers to. The download URL is only
+/// accepted if it is the asset's release on GitHub, since a committed
+/// lockfile could otherwise send downloads to any host.
+fn locked_asset(
+    spec: &BinarySpec,
+    tool: &LockedTool,
+    artifact: &LockedArtifact,
+) -> Result<ReleaseAsset, UpdateError> {
+    let untrusted = |reason: String| {
+        UpdateError::Download(
+            DownloadError::UntrustedLockedArtifact {
+                binary: spec.name.to_string(),
+                reason,
+            }
+            .into(),
+        )
+    };
+
+    if github::parse_release_version(&tool.tag).ok().as_ref() != Some(&tool.version) {
+        return Err(untrusted(format!(
+            "tag '{}' is not version {}",
+            tool.tag, tool.version
+        )));
+    }
+    let expected_name = platform::asset_name(spec.name);
+    if artifact.asset != expected_name {
+        return Err(untrusted(format!(
+            "asset '{}' is not {}",
+            artifact.asset, expected_name
+        )));
+    }
+    let url = github::release_asset_url(spec.repo, &tool.tag, &artifact.asset);
+    if artifact.url != url {
+        return Err(untrusted(format!("expected {}, found {}", url, artifact.url)));
+    }
+
+    Ok(ReleaseAsset {
+        name: artifact.asset.clone(),
+        browser_download_url: url,
+        size: artifact.size,
+        digest: None,
     })
 }
 
+/// Make a version already held in the versioned store the active one.
+/// No download is performed.
+pub fn activate_version(
+    spec: &BinarySpec,
+    version: &Version,
+    state: &mut AppState,
+    force: bool,
+) -> Result<(), UpdateError> {
+    policy::current().check_version(spec, version)?;
+    ensure_may_replace(spec, state, force)?;
+    // Keep the outgoing version in the store so it can be rolled back to
+    if let Some(current) = state.binaries.get(spec.name) {
+        let _ = store::adopt_active(spec.name, current);
+    }
+    store::activate(spec.name, version)?;
+    state.set_active(spec.name, version.clone(), platform::asset_name(spec.name));
+    state.set_hashes(spec.name, store::recorded_hashes(spec.name, version));
+    record_active_file(spec, state);
+    Ok(())
+}
+
+/// Restore the version a binary had before its active version last changed,
+/// together with its state entry. Returns the versions before and after.
+pub fn rollback_binary(
+    spec: &BinarySpec,
+    state: &mut AppState,
+    force: bool,
+) -> Result<(Version, Version), UpdateError> {
+    let started = Instant::now();
+    let (Some(current), Some(previous)) = (
+        state.installed_version(spec.name).cloned(),
+        state.previous.get(spec.name).map(|p| p.version.clone()),
+    ) else {
+        return Err(StorageError::NoPreviousVersion {
+            binary: spec.name.to_string(),
+        }
+        .into());
+    };
+
+    policy::current().check_version(spec, &previous)?;
+    ensure_may_replace(spec, state, force)?;
+    if let Some(entry) = state.binaries.get(spec.name) {
+        let _ = store::adopt_active(spec.name, entry);
+    }
+    store::activate(spec.name, &previous)?;
+    state.rollback(spec.name);
+    record_active_file(spec, state);
+
+    history::record(
+        history::Entry::new(Action::Rollback, spec.name, started)
+            .versions(Some(current.clone()), Some(previous.clone())),
+    );
+    Ok((current, previous))
+}
+
+/// Download the current platform's asset from a release into the versioned
+/// store, make it the active version, and record it in state.
+///
+/// Returns how the downloaded archive was verified.
+pub async fn install_release(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    release: &Release,
+    version: &Version,
+    state: &mut AppState,
+    force: bool,
+) -> Result<ChecksumStatus, UpdateError> {
+    // Check before downloading so a foreign binary fails fast
+    ensure_may_replace(spec, state, force)?;
+
+    // Keep the outgoing version in the store so `iii-cli use` can switch back
+    if let Some(current) = state.binaries.get(spec.name) {
+        let _ = store::adopt_active(spec.name, current);
+        if let Some(hashes) = &current.hashes {
+            if store::recorded_hashes(spec.name, &current.version).is_none() {
+                let _ = store::record_hashes(spec.name, &current.version, hashes);
+            }
+        }
+    }
+
+    // Download into the versioned store, then copy into the standard managed
+    // location (~/.local/bin/<name>), consistent with install.sh.
+    let (hashes, checksum) = store_release(client, spec, release, version, None).await?;
+    store::activate(spec.name, version)?;
+
+    state.record_install(spec.name, version.clone(), platform::asset_name(spec.name));
+    state.set_hashes(spec.name, Some(hashes));
+    record_active_file(spec, state);
+    Ok(checksum)
+}
+
+/// Download a version again and replace its stored copy, and the bin_dir()
+/// binary if it is the active version. When the archive hash was recorded at
+/// install time, the new download must match it exactly.
+pub async fn repair_version(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    version: &Version,
+    state: &mut AppState,
+) -> Result<(), UpdateError> {
+    let started = Instant::now();
+    platform::check_platform_support(spec)?;
+
+    let active = state.binaries.get(spec.name).filter(|b| &b.version == version);
+    let recorded = store::recorded_hashes(spec.name, version)
+        .or_else(|| active.and_then(|b| b.hashes.clone()));
+
+    // An intact stored copy can restore the active binary without a download
+    let stored = platform::versioned_binary_path(spec.name, version);
+    let (hashes, checksum) = match recorded {
+        Some(hashes)
+            if verify::check_file(&stored, Some(&hashes.binary_sha256)) == Integrity::Intact =>
+        {
+            (hashes, None)
+        }
+        _ => {
+            let release = github::fetch_release_by_version(client, spec, version).await?;
+            let expected = recorded.as_ref().map(|h| h.archive_sha256.as_str());
+            let (hashes, checksum) = store_release(client, spec, &release, version, expected).await?;
+            (hashes, Some(checksum))
+        }
+    };
+
+    if active.is_some() {
+        store::activate(spec.name, version)?;
+        state.set_hashes(spec.name, Some(hashes));
+        record_active_file(spec, state);
+    }
+
+    let mut entry = history::Entry::new(Action::Repair, spec.name, started)
+        .versions(Some(version.clone()), Some(version.clone()));
+    if let Some(checksum) = checksum {
+        entry = entry.download(platform::asset_name(spec.name), checksum);
+    }
+    history::record(entry);
+    Ok(())
+}
+
+/// Refuse to overwrite a binary in bin_dir() that iii-cli did not write, or
+/// that changed since, unless forced or confirmed interactively.
+///
+/// iii-cli's own binary is exempt: install.sh puts it in bin_dir() without
+/// a state entry, and self-update must still be able to replace it.
+fn ensure_may_replace(spec: &BinarySpec, state: &AppState, force: bool) -> Result<(), UpdateError> {
+    if spec.name == registry::SELF_SPEC.name {
+        return Ok(());
+    }
+    ownership::ensure_may_modify(
+        &platform::binary_path(spec.name),
+        state.binaries.get(spec.name),
+        "replace",
+        force,
+    )?;
+    Ok(())
+}
+
+/// Fingerprint the binary just written to bin_dir() so later updates and
+/// uninstalls can tell whether it is still the file iii-cli put there.
+fn record_active_file(spec: &BinarySpec, state: &mut AppState) {
+    let record = FileRecord::capture(&platform::binary_path(spec.name)).ok();
+    state.set_file_record(spec.name, record);
+}
+
+/// Download the current platform's asset from a release into the versioned
+/// store and record its hashes there. `expected_archive` overrides the
+/// release's own checksum with a previously recorded archive hash.
+///
+/// Returns the hashes and how the archive was verified.
+async fn store_release(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+    release: &Release,
+    version: &Version,
+    expected_archive: Option<&str>,
+) -> Result<(ArtifactHashes, ChecksumStatus), UpdateError> {
+    policy::current().check_version(spec, version)?;
+
+    // Find asset for current platform
+    let asset_name = platform::asset_name(spec.name);
+    let asset = github::find_asset(release, &asset_name).ok_or_else(|| {
+        UpdateError::Github(IiiGithubError::Network(
+            crate::error::NetworkError::AssetNotFound {
+                binary: spec.name.to_string(),
+                platform: platform::current_target().to_string(),
+            },
+        ))
+    })?;
+
+    // Find checksum asset in release (separate asset, not appended URL)
+    let checksum_url = if spec.has_checksum {
+        let checksum_name = platform::checksum_asset_name(spec.name);
+        github::find_asset(release, &checksum_name)
+            .map(|a| a.browser_download_url.clone())
+    } else {
+        None
+    };
+    let checksum = match (expected_archive, &checksum_url) {
+        (Some(expected), _) => Checksum::Recorded(expected),
+        (None, Some(url)) => Checksum::Sidecar(url),
+        (None, None) => Checksum::Unavailable,
+    };
+
+    let target_path = platform::versioned_binary_path(spec.name, version);
+    let hashes = download::download_and_install(
+        client,
+        spec,
+        asset,
+        &release.tag_name,
+        checksum,
+        &target_path,
+    )
+    .await?;
+    store::record_hashes(spec.name, version, &hashes)?;
+
+    Ok((hashes, checksum.status()))
+}
+
+/// Fetch the latest stable release, or the newest one the policy does not
+/// forbid if the latest is forbidden.
+async fn fetch_latest_allowed_release(
+    client: &reqwest::Client,
+    spec: &BinarySpec,
+) -> Result<(Release, Version), UpdateError> {
+    let release = github::fetch_latest_release(client, spec).await?;
+    let version = github::parse_release_version(&release.tag_name)
+        .map_err(|e| UpdateError::VersionParse(e.to_string()))?;
+
+    let policy = policy::current();
+    if !policy.is_forbidden(spec.name, &version) {
+        return Ok((release, version));
+    }
+
+    let releases = github::fetch_releases(client, spec).await?;
+    match github::find_matching_release(&releases, |v| !policy.is_forbidden(spec.name, v)) {
+        Some((allowed, release)) => Ok((release.clone(), allowed)),
+        None => {
+            policy.check_version(spec, &version)?;
+            Ok((release, version))
+        }
+    }
+}
+
 /// Update iii-cli itself to the latest version.
 pub async fn self_update(
     client: &reqwest::Client,
     state: &mut AppState,
 ) -> Result<UpdateResult, UpdateError> {
+    let started = Instant::now();
     let spec = &registry::SELF_SPEC;
 
+    policy::current().check_self_update()?;
     platform::check_platform_support(spec)?;
 
     eprintln!("  Checking for updates to {}...", spec.name);
 
-    let release = github::fetch_latest_release(client, spec).await?;
-    let latest_version = github::parse_release_version(&release.tag_name)
-        .map_err(|e| UpdateError::VersionParse(e.to_string()))?;
+    let (release, latest_version) = fetch_latest_allowed_release(client, spec).await?;
 
     // Use the installed binary version from state if available,
     // falling back to the compile-time version of the running binary.
@@ -253,44 +694,18 @@ pub async fn self_update(
         });
     }
 
-    let asset_name = platform::asset_name(spec.name);
-    let asset = github::find_asset(&release, &asset_name).ok_or_else(|| {
-        UpdateError::Github(IiiGithubError::Network(
-            crate::error::NetworkError::AssetNotFound {
-                binary: spec.name.to_string(),
-                platform: platform::current_target().to_string(),
-            },
-        ))
-    })?;
-
-    let checksum_url = if spec.has_checksum {
-        let checksum_name = platform::checksum_asset_name(spec.name);
-        github::find_asset(&release, &checksum_name)
-            .map(|a| a.browser_download_url.clone())
-    } else {
-        None
-    };
-
     eprintln!(
         "  Updating {} to v{}...",
         spec.name,
         latest_version
     );
 
-    // Install to the standard managed location (~/.local/bin/iii-cli),
-    // consistent with install.sh and other managed binaries.
-    let target_path = platform::binary_path(spec.name);
-
-    download::download_and_install(
-        client,
-        spec,
-        asset,
-        checksum_url.as_deref(),
-        &target_path,
-    )
-    .await?;
-
-    state.record_install(spec.name, latest_version.clone(), asset_name);
+    let checksum = install_release(client, spec, &release, &latest_version, state, true).await?;
+    history::record(
+        history::Entry::new(Action::Update, spec.name, started)
+            .versions(Some(current_version.clone()), Some(latest_version.clone()))
+            .download(platform::asset_name(spec.name), checksum),
+    );
 
     Ok(UpdateResult::Updated {
         binary: spec.name.to_string(),
@@ -303,16 +718,78 @@ pub async fn self_update(
 pub async fn update_all(
     client: &reqwest::Client,
     state: &mut AppState,
+    force: bool,
 ) -> Vec<Result<UpdateResult, UpdateError>> {
-    // Self-update first
-    let mut results = vec![self_update(client, state).await];
+    // Self-update first, unless policy leaves iii-cli updates to administrators
+    let mut results = Vec::new();
+    if policy::current().self_update_allowed() {
+        results.push(self_update(client, state).await);
+    }
 
     for spec in registry::all_binaries() {
-        results.push(update_binary(client, spec, state).await);
+        results.push(update_binary(client, spec, state, force).await);
     }
     results
 }
 
+/// Remove a managed binary from bin_dir(), all of its versioned copies, and
+/// its state entry.
+///
+/// Refuses to touch binaries iii-cli did not install: without a state entry
+/// or stored versions, nothing is removed (e.g., a binary found on PATH).
+/// A bin_dir() binary that changed since install is only removed when
+/// `force` is set or the user confirms.
+pub fn uninstall_binary(
+    spec: &BinarySpec,
+    state: &mut AppState,
+    force: bool,
+) -> Result<UninstallResult, UpdateError> {
+    let started = Instant::now();
+    let active = state.installed_version(spec.name).cloned();
+    let stored = store::stored_versions(spec.name);
+
+    if active.is_none() && stored.is_empty() {
+        let location = platform::find_existing_binary(spec.name)
+            .map(|p| format!(" (found at {})", p.display()))
+            .unwrap_or_default();
+        return Err(UpdateError::Storage(StorageError::NotManaged {
+            binary: spec.name.to_string(),
+            location,
+        }));
+    }
+
+    // Only remove the bin_dir() copy when state says iii-cli put it there
+    if active.is_some() {
+        let path = platform::binary_path(spec.name);
+        ownership::ensure_may_modify(&path, state.binaries.get(spec.name), "remove", force)?;
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(StorageError::from)?;
+        }
+    }
+
+    let removed_versions = store::remove_all(spec.name)?;
+    state.remove_binary(spec.name);
+    history::record(
+        history::Entry::new(Action::Uninstall, spec.name, started).versions(active.clone(), None),
+    );
+
+    Ok(UninstallResult {
+        binary: spec.name.to_string(),
+        active,
+        removed_versions,
+    })
+}
+
+/// Result of an uninstall operation.
+#[derive(Debug)]
+pub struct UninstallResult {
+    pub binary: String,
+    /// The version that was active in bin_dir(), if any
+    pub active: Option<Version>,
+    /// Versions removed from the versioned store
+    pub removed_versions: Vec<Version>,
+}
+
 /// Result of an update operation.
 #[derive(Debug)]
 pub enum UpdateResult {
@@ -325,6 +802,10 @@ pub enum UpdateResult {
         binary: String,
         version: Version,
     },
+    AlreadyInstalled {
+        binary: String,
+        version: Version,
+    },
 }
 
 /// Errors during update.
@@ -341,6 +822,15 @@ pub enum UpdateError {
 
     #[error(transparent)]
     Download(#[from] download::DownloadAndInstallError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+
+    #[error(transparent)]
+    Lock(#[from] LockError),
 }
 
 /// Print the result of an update operation.
@@ -372,8 +862,58 @@ pub fn print_update_result(result: &Result<UpdateResult, UpdateError>) {
                 version,
             );
         }
+        Ok(UpdateResult::AlreadyInstalled { binary, version }) => {
+            eprintln!(
+                "  {} {} v{} is already installed",
+                "✓".green(),
+                binary,
+                version,
+            );
+        }
         Err(e) => {
             eprintln!("  {} {}", "error:".red(), e);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked_tool(url: &str) -> LockedTool {
+        let asset = platform::asset_name("iii-console");
+        LockedTool {
+            name: "iii-console".to_string(),
+            version: Version::new(0, 2, 4),
+            tag: "v0.2.4".to_string(),
+            artifacts: vec![LockedArtifact {
+                target: platform::current_target().to_string(),
+                url: url.replace("{asset}", &asset),
+                asset,
+                sha256: "0".repeat(64),
+                size: 1024,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_locked_asset_must_be_github_release() {
+        let spec = &registry::REGISTRY[0];
+        let tool = locked_tool("https://github.com/iii-hq/console/releases/download/v0.2.4/{asset}");
+        let asset = locked_asset(spec, &tool, &tool.artifacts[0]).unwrap();
+        assert_eq!(asset.browser_download_url, tool.artifacts[0].url);
+
+        for url in [
+            "https://evil.example.com/iii-hq/console/releases/download/v0.2.4/{asset}",
+            "https://github.com/evil/console/releases/download/v0.2.4/{asset}",
+            "http://github.com/iii-hq/console/releases/download/v0.2.4/{asset}",
+        ] {
+            let tool = locked_tool(url);
+            assert!(locked_asset(spec, &tool, &tool.artifacts[0]).is_err(), "{}", url);
+        }
+
+        let mut tool = locked_tool("https://github.com/iii-hq/console/releases/download/v0.2.4/{asset}");
+        tool.tag = "v0.2.4/../../x".to_string();
+        assert!(locked_asset(spec, &tool, &tool.artifacts[0]).is_err());
+    }
+}
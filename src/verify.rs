@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use semver::Version;
+
+use crate::config::TamperCheck;
+use crate::ownership::{self, FileRecord};
+use crate::platform;
+use crate::state::AppState;
+use crate::store;
+
+/// Result of re-hashing one installed file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Integrity {
+    /// Matches the hash recorded at install time
+    Intact,
+    /// Differs from the recorded hash
+    Modified { expected: String, actual: String },
+    /// The file is gone
+    Missing,
+    /// Installed before hashes were recorded; nothing to compare against
+    Unrecorded,
+}
+
+impl Integrity {
+    /// Whether the file needs repair.
+    pub fn is_damaged(&self) -> bool {
+        matches!(self, Self::Modified { .. } | Self::Missing)
+    }
+}
+
+/// One verified file: the active binary in bin_dir() or a stored version.
+#[derive(Debug)]
+pub struct FileCheck {
+    pub binary: String,
+    pub version: Version,
+    pub path: PathBuf,
+    /// The bin_dir() copy rather than the versioned store copy
+    pub active: bool,
+    pub integrity: Integrity,
+}
+
+/// Re-hash the active binary and every stored version of a managed binary.
+pub fn verify_binary(binary_name: &str, state: &AppState) -> Vec<FileCheck> {
+    let mut checks = Vec::new();
+
+    if let Some(entry) = state.binaries.get(binary_name) {
+        let path = platform::binary_path(binary_name);
+        let expected = entry.hashes.as_ref().map(|h| h.binary_sha256.as_str());
+        checks.push(FileCheck {
+            binary: binary_name.to_string(),
+            version: entry.version.clone(),
+            integrity: check_file(&path, expected),
+            path,
+            active: true,
+        });
+    }
+
+    for version in store::stored_versions(binary_name) {
+        let path = platform::versioned_binary_path(binary_name, &version);
+        let recorded = store::recorded_hashes(binary_name, &version);
+        let expected = recorded.as_ref().map(|h| h.binary_sha256.as_str());
+        checks.push(FileCheck {
+            binary: binary_name.to_string(),
+            integrity: check_file(&path, expected),
+            version,
+            path,
+            active: false,
+        });
+    }
+
+    checks
+}
+
+/// Compare a file's SHA256 against the expected hash.
+pub fn check_file(path: &Path, expected: Option<&str>) -> Integrity {
+    if !path.exists() {
+        return Integrity::Missing;
+    }
+    let Some(expected) = expected else {
+        return Integrity::Unrecorded;
+    };
+    match ownership::hash_file(path) {
+        Ok(actual) if actual.eq_ignore_ascii_case(expected) => Integrity::Intact,
+        Ok(actual) => Integrity::Modified {
+            expected: expected.to_string(),
+            actual,
+        },
+        // An unreadable binary cannot be trusted either
+        Err(e) => Integrity::Modified {
+            expected: expected.to_string(),
+            actual: format!("unreadable ({})", e),
+        },
+    }
+}
+
+/// Whether a managed binary may run, according to `exec.tamper_check`. The
+/// binary is compared to the fingerprint recorded when it was installed:
+/// size, mtime and inode first, and its hash only when they differ. A
+/// modified binary gets a warning, or is refused in strict mode. Returns true
+/// when there is nothing to compare against.
+pub fn allow_run(path: &Path, recorded: Option<&FileRecord>, mode: TamperCheck) -> bool {
+    let Some(recorded) = recorded else {
+        return true;
+    };
+    if mode == TamperCheck::Off {
+        return true;
+    }
+    // A missing binary fails to run with its own error
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return true;
+    };
+    // An unreadable binary cannot be trusted either
+    if recorded.metadata_matches(&metadata)
+        || ownership::hash_file(path).is_ok_and(|actual| actual.eq_ignore_ascii_case(&recorded.sha256))
+    {
+        return true;
+    }
+
+    if mode == TamperCheck::Strict {
+        eprintln!(
+            "{} {} was modified after iii-cli installed it; refusing to run it (exec.tamper_check = strict)",
+            "error:".red(),
+            path.display()
+        );
+        eprintln!(
+            "  {} run `iii-cli verify --repair` to restore it",
+            "hint:".dimmed()
+        );
+        return false;
+    }
+
+    eprintln!(
+        "  {} {} was modified after iii-cli installed it (run `iii-cli verify` to check it)",
+        "warning:".yellow(),
+        path.display()
+    );
+    true
+}
+
+/// Print verification results.
+pub fn print_checks(checks: &[FileCheck]) {
+    for check in checks {
+        let location = if check.active { "active" } else { "stored" };
+        let label = format!("{} v{} ({})", check.binary, check.version, location);
+        match &check.integrity {
+            Integrity::Intact => eprintln!("  {} {}", "✓".green(), label),
+            Integrity::Unrecorded => eprintln!(
+                "  {} {} — no hash recorded (installed by an older iii-cli)",
+                "?".dimmed(),
+                label
+            ),
+            Integrity::Missing => eprintln!(
+                "  {} {} — missing: {}",
+                "✗".red(),
+                label,
+                check.path.display()
+            ),
+            Integrity::Modified { expected, actual } => {
+                eprintln!(
+                    "  {} {} — modified: {}",
+                    "✗".red(),
+                    label,
+                    check.path.display()
+                );
+                eprintln!("      expected sha256 {}", expected.dimmed());
+                eprintln!("      actual   sha256 {}", actual.dimmed());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"binary").unwrap();
+        let hash = ownership::hash_file(&path).unwrap();
+
+        assert_eq!(check_file(&path, Some(&hash)), Integrity::Intact);
+        assert_eq!(
+            check_file(&path, Some(&hash.to_uppercase())),
+            Integrity::Intact
+        );
+        assert_eq!(check_file(&path, None), Integrity::Unrecorded);
+        assert_eq!(
+            check_file(&dir.path().join("missing"), Some(&hash)),
+            Integrity::Missing
+        );
+
+        std::fs::write(&path, b"tampered").unwrap();
+        let result = check_file(&path, Some(&hash));
+        assert!(matches!(result, Integrity::Modified { .. }));
+        assert!(result.is_damaged());
+    }
+
+    #[test]
+    fn test_allow_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"binary").unwrap();
+        let record = FileRecord::capture(&path).unwrap();
+
+        for mode in [TamperCheck::Off, TamperCheck::Warn, TamperCheck::Strict] {
+            assert!(allow_run(&path, Some(&record), mode));
+            assert!(allow_run(&path, None, mode));
+        }
+
+        std::fs::write(&path, b"tampered").unwrap();
+        assert!(allow_run(&path, Some(&record), TamperCheck::Off));
+        assert!(allow_run(&path, Some(&record), TamperCheck::Warn));
+        assert!(!allow_run(&path, Some(&record), TamperCheck::Strict));
+    }
+
+    #[test]
+    fn test_allow_run_hashes_only_when_metadata_differs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("iii");
+        std::fs::write(&path, b"binary").unwrap();
+
+        // Matching metadata is trusted without reading the file
+        let mut record = FileRecord::capture(&path).unwrap();
+        record.sha256 = "0".repeat(64);
+        assert!(allow_run(&path, Some(&record), TamperCheck::Strict));
+
+        // Otherwise the hash decides
+        record.size += 1;
+        assert!(!allow_run(&path, Some(&record), TamperCheck::Strict));
+        record.sha256 = ownership::hash_file(&path).unwrap();
+        assert!(allow_run(&path, Some(&record), TamperCheck::Strict));
+    }
+}